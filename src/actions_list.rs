@@ -1,7 +1,11 @@
 use gitcg_sim::prelude::{card_defs::Cost, *};
 use yew::prelude::*;
 
-use crate::app::{AppAction, AppState, GameStateProp};
+use crate::{
+    analysis::{current_analysis, current_hint},
+    app::{AppAction, AppState, GameStateProp},
+    tooltip::{CardDetail, SkillDetail, Tooltip},
+};
 
 #[derive(Properties, PartialEq)]
 pub struct ActionsListProps {
@@ -23,6 +27,12 @@ pub fn actions_list(props: &ActionsListProps) -> Html {
         };
     }
     let acts = app.game_state.actions();
+    let analysis = current_analysis(app);
+    let hint = current_hint(app);
+    let total_visits = analysis
+        .as_ref()
+        .map(|a| a.total_visits())
+        .unwrap_or_default();
     html! {
         <div class="actions-list">
             <table>
@@ -32,15 +42,57 @@ pub fn actions_list(props: &ActionsListProps) -> Html {
                     <th>{"Target"}</th>
                     <th style="min-width: 62px;">{"Cost"}</th>
                     <th>{"Action"}</th>
+                    {if analysis.is_some() {
+                        html! {
+                            <>
+                                <th title="Share of root visits">{"Visits"}</th>
+                                <th title="Mean value from the search">{"Win %"}</th>
+                            </>
+                        }
+                    } else {
+                        html! {}
+                    }}
+                    {if hint.is_some() {
+                        html! { <th title="Recommended moves and their share of root visits">{"Hint"}</th> }
+                    } else {
+                        html! {}
+                    }}
                 </thead>
                 <tbody>
                     {for acts.iter().enumerate().map({
                         let game_state = GameStateProp::new(&app.game_state.game_state);
-                        let disabled = app.game_state.to_move() == Some(PlayerId::PlayerSecond);
+                        let disabled = !app.is_human_to_move(&app.game_state);
                         move |(i, &action)| {
                             let onclick = perform_action(action);
+                            let stats_cells = analysis.as_ref().map(|analysis| {
+                                let stats = analysis.action_stats(action);
+                                let visits = stats.map(|s| s.visits).unwrap_or_default();
+                                let share = if total_visits > 0 {
+                                    100.0 * (visits as f32) / (total_visits as f32)
+                                } else {
+                                    0.0
+                                };
+                                let is_best = analysis.pv.first() == Some(&action);
+                                html! {
+                                    <>
+                                        <td class={classes!("analysis-visits", if is_best { Some("is-best") } else { None })}>
+                                            {format!("{visits} ({share:.0}%)")}
+                                        </td>
+                                        <td class="analysis-value">
+                                            {stats.map(|s| format!("{:.1}%", 100.0 * s.value)).unwrap_or_default()}
+                                        </td>
+                                    </>
+                                }
+                            });
+                            let hint_rank = hint.as_ref().and_then(|hint| hint.rank(action));
+                            let hint_cell = hint.as_ref().map(|_| html! {
+                                <td class="hint-rank">
+                                    {hint_rank.map(|(rank, share)| format!("#{} ({:.0}%)", rank + 1, 100.0 * share)).unwrap_or_default()}
+                                </td>
+                            });
+                            let is_hint = matches!(hint_rank, Some((0, _)));
                             html! {
-                                <tr>
+                                <tr class={classes!(if is_hint { Some("is-hint") } else { None })}>
                                     <td>{format!("{}", i + 1)}</td>
                                     <td>
                                         <button {onclick} {disabled}>
@@ -50,6 +102,8 @@ pub fn actions_list(props: &ActionsListProps) -> Html {
                                     <td><ActionTarget {action} game_state={game_state.clone()} /></td>
                                     <td><CostInfo cost={game_state.action_info(action).0} /></td>
                                     <td><ActionType {action} game_state={game_state.clone()} /></td>
+                                    {for stats_cells}
+                                    {for hint_cell}
                                 </tr>
                             }
                         }
@@ -73,14 +127,18 @@ pub fn action_name(props: &ActionProps) -> Html {
             PlayerAction::CastSkill(skill_id) => {
                 html! {
                     <span class="action action-cast-skill" title="Cast Skill">
-                        {skill_id.skill().name}
+                        <Tooltip content={html! { <SkillDetail {skill_id} /> }}>
+                            {skill_id.skill().name}
+                        </Tooltip>
                     </span>
                 }
             }
             PlayerAction::PlayCard(card_id, _) => {
                 html! {
                     <span class="action action-play-card" title="Play Card">
-                        <span class="card-name">{card_id.card().name}</span>
+                        <Tooltip content={html! { <CardDetail {card_id} /> }}>
+                            <span class="card-name">{card_id.card().name}</span>
+                        </Tooltip>
                     </span>
                 }
             }
@@ -0,0 +1,134 @@
+use std::rc::Rc;
+
+use gitcg_sim::prelude::*;
+use yew::prelude::*;
+
+use crate::{
+    app::{describe_action, AppState},
+    search::{best_value, ActionStats, SearchProgress},
+};
+
+/// Search results for the position of a human player, shown without playing any move.
+#[derive(Clone)]
+pub struct Analysis {
+    /// Zobrist hash of the analyzed position.
+    pub hash: u64,
+    /// Win rate of the analyzed player, from the value of the most visited move.
+    pub win_rate: Option<f32>,
+    pub pv: Vec<Input>,
+    pub action_stats: Vec<ActionStats>,
+    pub summary: String,
+    pub finished: bool,
+}
+
+impl Analysis {
+    pub fn new(progress: &SearchProgress, finished: bool) -> Self {
+        let res = &progress.result;
+        Self {
+            hash: progress.hash,
+            win_rate: best_value(&progress.action_stats),
+            pv: res.pv.into_iter().copied().collect(),
+            action_stats: progress.action_stats.clone(),
+            summary: format!(
+                "{}, {} states visited",
+                res.counter.summary(progress.total_time_ns),
+                res.counter.states_visited
+            ),
+            finished,
+        }
+    }
+
+    pub fn total_visits(&self) -> u32 {
+        self.action_stats.iter().map(|s| s.visits).sum()
+    }
+
+    pub fn action_stats(&self, action: Input) -> Option<&ActionStats> {
+        self.action_stats.iter().find(|s| s.action == action)
+    }
+}
+
+/// Number of moves recommended by a hint.
+const HINT_MOVES: usize = 3;
+
+/// The most visited moves from a short search of a human player's position, with their share of
+/// the visits. The first move is the recommended one.
+#[derive(Clone)]
+pub struct Hint {
+    /// Zobrist hash of the position the hint is for.
+    pub hash: u64,
+    pub moves: Vec<(Input, f32)>,
+}
+
+impl Hint {
+    pub fn new(progress: &SearchProgress) -> Self {
+        let total_visits: u32 = progress.action_stats.iter().map(|s| s.visits).sum();
+        let mut stats = progress.action_stats.clone();
+        stats.sort_by_key(|s| std::cmp::Reverse(s.visits));
+        Self {
+            hash: progress.hash,
+            moves: stats
+                .into_iter()
+                .take(HINT_MOVES)
+                .map(|s| (s.action, s.visits as f32 / total_visits.max(1) as f32))
+                .collect(),
+        }
+    }
+
+    pub fn best(&self) -> Option<Input> {
+        self.moves.first().map(|&(action, _)| action)
+    }
+
+    /// Rank (starting from 0) and visit share of the action, if it is among the recommended moves.
+    pub fn rank(&self, action: Input) -> Option<(usize, f32)> {
+        self.moves
+            .iter()
+            .enumerate()
+            .find(|(_, (a, _))| *a == action)
+            .map(|(i, &(_, share))| (i, share))
+    }
+}
+
+/// Returns the hint if it applies to the current position.
+pub fn current_hint(app: &AppState) -> Option<Rc<Hint>> {
+    app.hint
+        .clone()
+        .filter(|h| h.hash == app.game_state.zobrist_hash())
+}
+
+/// Returns the analysis if it applies to the current position.
+pub fn current_analysis(app: &AppState) -> Option<Rc<Analysis>> {
+    app.analysis
+        .clone()
+        .filter(|a| a.hash == app.game_state.zobrist_hash())
+}
+
+#[derive(Properties, PartialEq)]
+pub struct AnalysisPanelProps {
+    pub app: UseReducerHandle<AppState>,
+}
+
+#[function_component(AnalysisPanel)]
+pub fn analysis_panel(props: &AnalysisPanelProps) -> Html {
+    let app = &props.app;
+    let Some(analysis) = current_analysis(app) else {
+        return html! { <div class="analysis"><em>{"Analyzing..."}</em></div> };
+    };
+    let pv: Vec<String> = analysis
+        .pv
+        .iter()
+        .map(|&a| describe_action(&app.game_state, a))
+        .collect();
+    html! {
+        <div class="analysis">
+            <div>
+                {"Win rate: "}
+                <span class="analysis-eval">
+                    {analysis.win_rate.map(|v| format!("{:.1}%", 100.0 * v)).unwrap_or_else(|| "-".to_string())}
+                </span>
+                {if analysis.finished { "" } else { " (searching)" }}
+            </div>
+            <div>{"Principal Variation: "}{pv.join(", ")}</div>
+            <div class="analysis-summary">{&analysis.summary}</div>
+        </div>
+    }
+}
@@ -1,6 +1,7 @@
 use std::{
     borrow::Borrow,
     cell::{RefCell, RefMut},
+    collections::{BTreeMap, VecDeque},
     ops::Deref,
     rc::Rc,
 };
@@ -11,15 +12,30 @@ use gitcg_sim::{
     rand::prelude::*,
     smallvec::smallvec,
 };
+use gloo::events::EventListener;
 use gloo_storage::{LocalStorage, Storage};
+use serde::{Deserialize, Serialize};
+use wasm_bindgen::JsCast;
 use yew::prelude::*;
-use yew_agent::Bridged;
 
 use crate::components::*;
 use crate::{
     actions_list::*,
+    analysis::{Analysis, AnalysisPanel, Hint},
     deck_editor::{DeckEditor, DeckSelector, Decks},
+    determinization::Determinization,
+    explanation::MoveExplanation,
+    move_log::MoveLog,
+    redraw::{ai_redraw_selection, redraw, PendingRedraw, RedrawSelection},
+    replay::{advance_until, answer_nondet, Replay, ReplayControls},
+    reroll::{ai_reroll_selection, dice_vec, is_roll_request, PendingReroll, RerollControls},
+    review::{Review, ReviewPanel},
     search::*,
+    search_tree::SearchTree,
+    settings::{Difficulty, SearchSettings, SearchSettingsForm},
+    starting_character::{PendingStart, StartingCharacterSelection},
+    state_edit::StateEdit,
+    win_graph::{win_estimate, WinProbabilityGraph},
 };
 
 pub type G = GameStateWrapper<StandardNondetHandlerState>;
@@ -27,20 +43,392 @@ pub type G = GameStateWrapper<StandardNondetHandlerState>;
 pub enum AppAction {
     PerformAction(Input),
     SetMessage(String),
-    SetGameState(Rc<G>),
+    NewGame(Rc<(Decklist, Decklist)>, u64),
+    /// Choose the starting characters before starting a new game.
+    PrepareGame(Rc<(Decklist, Decklist)>, u64),
+    ChooseStartingCharacter(PlayerId, u8),
+    StartingCharacterEvaluated(PlayerId, u8, Option<f32>),
+    /// The search of an AI starting character candidate was stopped before it finished.
+    StartingCharacterAbandoned(PlayerId, u8),
+    LoadReplay(Rc<Replay>),
+    SetController(PlayerId, Controller),
+    SetSearchSettings(SearchSettings),
+    SetAnalyze(bool),
+    SetPonder(bool),
+    SetAnalysis(Option<Rc<Analysis>>),
+    SetSearchTree(Option<Rc<SearchTreeNode>>),
+    SetExplanation(Option<Rc<MoveExplanation>>),
+    SetHint(Option<Rc<Hint>>),
+    /// Restart the search for the current position after a one-off search replaced it.
+    ResumeSearch,
+    SetWinEstimate(usize, f32),
+    StartReview,
+    ReviewResult(usize, SearchProgress),
+    /// Redraw the cards of the opening hand at the given indices for the seat whose redraw is pending.
+    Redraw(Vec<usize>),
+    /// The seat whose redraw is pending keeps its opening hand.
+    KeepHand,
+    /// Reroll the dice at the given indices for the seat whose reroll is pending.
+    Reroll(Vec<usize>),
+    /// The seat whose reroll is pending keeps its dice.
+    KeepDice,
+    /// Skip the review of the move at the given index, whose search failed or was stopped.
+    SkipReview(usize),
+    /// Show the position before the move at the given index of the move log, or the current
+    /// position for `None`.
+    SelectMove(Option<usize>),
+    Undo,
+    Redo,
+}
+
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Serialize, Deserialize)]
+pub enum Controller {
+    Human,
+    Ai(Difficulty),
+}
+
+impl Controller {
+    pub const ALL: [Controller; 6] = [
+        Controller::Human,
+        Controller::Ai(Difficulty::Beginner),
+        Controller::Ai(Difficulty::Normal),
+        Controller::Ai(Difficulty::Hard),
+        Controller::Ai(Difficulty::Max),
+        Controller::Ai(Difficulty::Custom),
+    ];
+
+    pub fn name(self) -> String {
+        match self {
+            Controller::Human => "Human".to_string(),
+            Controller::Ai(difficulty) => format!("AI ({})", difficulty.name()),
+        }
+    }
+}
+
+/// Who controls each seat, for Player 1 and Player 2 respectively.
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Serialize, Deserialize)]
+pub struct Controllers(pub Controller, pub Controller);
+
+impl Default for Controllers {
+    fn default() -> Self {
+        Self(Controller::Human, Controller::Ai(Difficulty::Custom))
+    }
+}
+
+impl Controllers {
+    pub fn get_from_storage() -> Self {
+        LocalStorage::get(CONTROLLERS_KEY).unwrap_or_default()
+    }
+
+    pub fn get(&self, player_id: PlayerId) -> Controller {
+        match player_id {
+            PlayerId::PlayerFirst => self.0,
+            PlayerId::PlayerSecond => self.1,
+        }
+    }
+
+    pub fn set(&mut self, player_id: PlayerId, controller: Controller) {
+        match player_id {
+            PlayerId::PlayerFirst => self.0 = controller,
+            PlayerId::PlayerSecond => self.1 = controller,
+        }
+    }
+
+    pub fn is_ai(&self, player_id: PlayerId) -> bool {
+        self.difficulty(player_id).is_some()
+    }
+
+    pub fn difficulty(&self, player_id: PlayerId) -> Option<Difficulty> {
+        match self.get(player_id) {
+            Controller::Human => None,
+            Controller::Ai(difficulty) => Some(difficulty),
+        }
+    }
+
+    pub fn has_human(&self) -> bool {
+        self.0 == Controller::Human || self.1 == Controller::Human
+    }
+
+    /// A player's hand is hidden when they are played by the AI against a human.
+    pub fn is_hand_hidden(&self, player_id: PlayerId) -> bool {
+        self.is_ai(player_id) && !self.is_ai(opponent(player_id))
+    }
+}
+
+pub fn opponent(player_id: PlayerId) -> PlayerId {
+    match player_id {
+        PlayerId::PlayerFirst => PlayerId::PlayerSecond,
+        PlayerId::PlayerSecond => PlayerId::PlayerFirst,
+    }
+}
+
+/// Maximum number of previous game states kept for undo.
+const MAX_UNDO_HISTORY: usize = 200;
+
+/// An input applied to the game, along with the game state before it was applied.
+#[derive(Clone)]
+pub struct MoveLogEntry {
+    pub game_state: Rc<G>,
+    pub action: Input,
+    /// Nondeterministic results drawn while applying `action`.
+    pub nondet: Vec<Input>,
+    /// Edits made to the game state after applying `action`.
+    pub edits: Vec<StateEdit>,
 }
 
 #[derive(Clone)]
 pub struct AppState {
+    pub decklists: Rc<(Decklist, Decklist)>,
+    pub seed: u64,
+    pub controllers: Controllers,
+    pub search_settings: SearchSettings,
+    /// Whether to search the position while a human player is to move.
+    pub analyze: bool,
+    /// Whether the AI keeps searching while its human opponent is to move.
+    pub ponder: bool,
+    pub analysis: Option<Rc<Analysis>>,
+    /// Search tree of the most recently finished search.
+    pub search_tree: Option<Rc<SearchTreeNode>>,
+    /// Explanation of the latest move played by the AI.
+    pub explanation: Option<Rc<MoveExplanation>>,
+    /// Recommended moves for a human player, shown in the actions list.
+    pub hint: Option<Rc<Hint>>,
+    /// Post-game review of the human moves, once started.
+    pub review: Option<Rc<Review>>,
+    /// Estimated win probability of `PlayerFirst` by move log index of the position.
+    pub win_estimates: BTreeMap<usize, f32>,
+    /// New game waiting for the choice of starting characters.
+    pub pending_start: Option<Rc<PendingStart>>,
+    /// Incremented to restart the search for the current position.
+    pub search_generation: u32,
+    /// Human seats choosing cards of the opening hand to redraw.
+    pub pending_redraw: Option<PendingRedraw>,
+    /// Dice of the Roll Phase held back from the simulator while the seats reroll.
+    pub pending_reroll: Option<PendingReroll>,
+    /// Edits made to the game state before the first move.
+    pub initial_edits: Vec<StateEdit>,
     pub game_state: Rc<G>,
     pub message: String,
+    pub undo_stack: VecDeque<Rc<G>>,
+    pub redo_stack: Vec<(Rc<G>, MoveLogEntry)>,
+    pub move_log: Vec<MoveLogEntry>,
+    /// Index into the move log of the position shown on the board, or `None` for the current position.
+    pub replay_index: Option<usize>,
 }
 
 impl Default for AppState {
     fn default() -> Self {
-        Self {
-            game_state: Rc::new(default_game_state()),
+        let decklists = Rc::new(default_decklists());
+        let seed = DEFAULT_RANDOM_SEED;
+        let mut state = Self {
+            game_state: Rc::new(new_game(&decklists, seed)),
+            decklists,
+            seed,
+            controllers: Controllers::get_from_storage(),
+            search_settings: SearchSettings::get_from_storage(),
+            analyze: false,
+            ponder: LocalStorage::get(PONDER_KEY).unwrap_or_default(),
+            analysis: None,
+            search_tree: None,
+            explanation: None,
+            hint: None,
+            review: None,
+            win_estimates: Default::default(),
+            pending_start: None,
+            search_generation: 0,
+            pending_redraw: None,
+            pending_reroll: None,
+            initial_edits: Default::default(),
             message: Default::default(),
+            undo_stack: Default::default(),
+            redo_stack: Default::default(),
+            move_log: Default::default(),
+            replay_index: None,
+        };
+        state.start_redraw();
+        state
+    }
+}
+
+impl AppState {
+    pub fn can_undo(&self) -> bool {
+        !self.undo_stack.is_empty()
+    }
+
+    pub fn can_redo(&self) -> bool {
+        !self.redo_stack.is_empty()
+    }
+
+    fn push_undo(&mut self, game_state: Rc<G>) {
+        if self.undo_stack.len() >= MAX_UNDO_HISTORY {
+            self.undo_stack.pop_front();
+        }
+        self.undo_stack.push_back(game_state);
+    }
+
+    pub fn is_ai_to_move(&self, game_state: &G) -> bool {
+        game_state
+            .to_move()
+            .map_or(false, |player_id| self.controllers.is_ai(player_id))
+    }
+
+    pub fn is_human_to_move(&self, game_state: &G) -> bool {
+        game_state
+            .to_move()
+            .map_or(false, |player_id| !self.controllers.is_ai(player_id))
+    }
+
+    /// Whether the game is waiting on a step outside of the simulator, such as the choice of
+    /// starting characters for a new game, a redraw or a reroll, before the player to move can act.
+    pub fn is_setup_pending(&self) -> bool {
+        self.pending_start.is_some()
+            || self.pending_redraw.is_some()
+            || self.pending_reroll.is_some()
+    }
+
+    fn cancel_setup(&mut self) {
+        self.pending_redraw = None;
+        self.pending_reroll = None;
+    }
+
+    /// Apply an edit to the current game state and record it in the move log.
+    fn apply_edit(&mut self, edit: StateEdit) {
+        let mut game_state: G = self.game_state.deref().clone();
+        edit.apply(&mut game_state);
+        self.game_state = game_state.into();
+        match self.move_log.last_mut() {
+            Some(entry) => entry.edits.push(edit),
+            None => self.initial_edits.push(edit),
+        }
+    }
+
+    /// Redraw the cards of the player's hand at the given indices.
+    fn redraw(&mut self, player_id: PlayerId, selected: &[usize]) {
+        // The redraw rebuilds the deck, so it must happen before any card was drawn.
+        if selected.is_empty() || !self.move_log.is_empty() {
+            return;
+        }
+        let decklist = match player_id {
+            PlayerId::PlayerFirst => &self.decklists.0,
+            PlayerId::PlayerSecond => &self.decklists.1,
+        };
+        let seed = self.seed ^ self.game_state.zobrist_hash();
+        let edit = redraw(&self.game_state, decklist, player_id, selected, seed);
+        self.apply_edit(edit);
+    }
+
+    /// Start the redraw of the opening hands. AI seats redraw right away and human seats choose
+    /// their cards in turn.
+    fn start_redraw(&mut self) {
+        let mut human_players = vec![];
+        for player_id in [PlayerId::PlayerFirst, PlayerId::PlayerSecond] {
+            if self.controllers.is_ai(player_id) {
+                let selected = ai_redraw_selection(&self.game_state, player_id);
+                self.redraw(player_id, &selected);
+            } else {
+                human_players.push(player_id);
+            }
+        }
+        self.pending_redraw = PendingRedraw::new(human_players);
+    }
+
+    /// Start the reroll of the dice the simulator requested for the Roll Phase. AI seats reroll
+    /// right away and human seats choose their dice in turn.
+    fn start_reroll(&mut self, request: NondetRequest, result: NondetResult) {
+        let mut pending = PendingReroll::new(request, result);
+        let seed = self.seed ^ self.game_state.zobrist_hash();
+        for player_id in [PlayerId::PlayerFirst, PlayerId::PlayerSecond] {
+            let chances = pending.chances(player_id);
+            if !self.controllers.is_ai(player_id) {
+                if chances > 0 {
+                    pending.players.push((player_id, chances));
+                }
+                continue;
+            }
+            for _ in 0..chances {
+                let dice = pending.dice(player_id);
+                let selected = ai_reroll_selection(&self.game_state, player_id, &dice);
+                if selected.is_empty() {
+                    break;
+                }
+                pending.reroll(&self.game_state, player_id, &selected, seed);
+            }
+        }
+        self.pending_reroll = Some(pending);
+        self.finish_reroll();
+    }
+
+    /// Once every seat is done, answer the roll request with the rerolled dice and resolve the
+    /// rest of the last move, recording the results in its move log entry.
+    fn finish_reroll(&mut self) {
+        let Some(pending) = self.pending_reroll.take() else {
+            return;
+        };
+        if !pending.is_done() {
+            self.pending_reroll = Some(pending);
+            return;
+        }
+        let mut game_state: G = self.game_state.deref().clone();
+        let mut nondet = vec![];
+        match answer_nondet(
+            &mut game_state,
+            pending.result(),
+            &mut nondet,
+            is_roll_request,
+        ) {
+            Ok(held) => {
+                self.game_state = game_state.into();
+                if let Some(entry) = self.move_log.last_mut() {
+                    entry.nondet.extend(nondet);
+                }
+                if let Some((request, result)) = held {
+                    self.start_reroll(request, result);
+                }
+            }
+            Err(e) => self.message = format!("Failed to roll the dice: {e:?}"),
+        }
+    }
+
+    /// Undo and redo skip over positions waiting on the AI so that the AI does
+    /// not immediately replay its move, unless there is no human at the table.
+    fn is_skipped_by_undo(&self, game_state: &G) -> bool {
+        self.controllers.has_human() && self.is_ai_to_move(game_state)
+    }
+
+    /// Start a new game, applying `edits` such as the choice of starting characters to the
+    /// created game state before the redraw.
+    fn start_game(
+        &mut self,
+        decklists: Rc<(Decklist, Decklist)>,
+        seed: u64,
+        edits: Vec<StateEdit>,
+    ) {
+        self.game_state = new_game(&decklists, seed).into();
+        self.decklists = decklists;
+        self.seed = seed;
+        self.undo_stack.clear();
+        self.redo_stack.clear();
+        self.move_log.clear();
+        self.explanation = None;
+        self.review = None;
+        self.win_estimates.clear();
+        self.pending_start = None;
+        self.replay_index = None;
+        self.initial_edits.clear();
+        for edit in edits {
+            self.apply_edit(edit);
+        }
+        self.cancel_setup();
+        self.start_redraw();
+    }
+
+    /// Start the pending game once every seat has chosen its starting character.
+    fn start_pending_game(&mut self) {
+        let Some(pending) = self.pending_start.clone() else {
+            return;
+        };
+        if pending.is_complete() {
+            self.start_game(pending.decklists.clone(), pending.seed, pending.edits());
         }
     }
 }
@@ -57,21 +445,214 @@ impl Reducible for AppState {
     fn reduce(self: Rc<Self>, action: Self::Action) -> Rc<Self> {
         let mut next: Self = self.deref().clone();
         match action {
-            AppAction::SetGameState(game_state) => {
-                next.game_state = game_state;
+            AppAction::NewGame(decklists, seed) => next.start_game(decklists, seed, vec![]),
+            AppAction::PrepareGame(decklists, seed) => {
+                let pending = PendingStart::new(decklists, seed, &next.controllers);
+                next.pending_start = Some(Rc::new(pending));
+                next.review = None;
+                next.cancel_setup();
+                next.start_pending_game();
+            }
+            AppAction::ChooseStartingCharacter(player_id, index) => {
+                if let Some(pending) = &mut next.pending_start {
+                    Rc::make_mut(pending).choose(player_id, index);
+                }
+                next.start_pending_game();
+            }
+            AppAction::StartingCharacterEvaluated(player_id, index, value) => {
+                if let Some(pending) = &mut next.pending_start {
+                    Rc::make_mut(pending).set_evaluation(player_id, index, value);
+                }
+                next.start_pending_game();
+            }
+            AppAction::StartingCharacterAbandoned(player_id, index) => {
+                if let Some(pending) = &mut next.pending_start {
+                    Rc::make_mut(pending).restart_evaluation(player_id, index);
+                }
+            }
+            AppAction::LoadReplay(replay) => match replay.reconstruct() {
+                Ok((game_state, move_log, initial_edits)) => {
+                    next.message = format!("Loaded replay: {} moves", move_log.len());
+                    next.decklists = Rc::new(replay.decklists.clone());
+                    next.seed = replay.seed;
+                    next.game_state = game_state.into();
+                    next.undo_stack = move_log
+                        .iter()
+                        .rev()
+                        .take(MAX_UNDO_HISTORY)
+                        .rev()
+                        .map(|entry| entry.game_state.clone())
+                        .collect();
+                    next.redo_stack.clear();
+                    next.move_log = move_log;
+                    next.replay_index = None;
+                    next.initial_edits = initial_edits;
+                    next.cancel_setup();
+                    next.review = None;
+                    next.win_estimates.clear();
+                }
+                Err(e) => {
+                    next.message = format!("Failed to load replay: {e}");
+                }
+            },
+            AppAction::SetController(player_id, controller) => {
+                next.controllers.set(player_id, controller);
+                if let Err(e) = LocalStorage::set(CONTROLLERS_KEY, next.controllers) {
+                    gloo::console::error!(format!("Can't save into localStorage: {:#?}", e));
+                }
+            }
+            AppAction::SetSearchSettings(search_settings) => {
+                search_settings.save_to_storage();
+                next.search_settings = search_settings;
+            }
+            AppAction::SetAnalyze(analyze) => {
+                next.analyze = analyze;
+                if !analyze {
+                    next.analysis = None;
+                }
+            }
+            AppAction::SetPonder(ponder) => {
+                next.ponder = ponder;
+                if let Err(e) = LocalStorage::set(PONDER_KEY, ponder) {
+                    gloo::console::error!(format!("Can't save into localStorage: {:#?}", e));
+                }
+            }
+            AppAction::SetAnalysis(analysis) => {
+                next.analysis = analysis;
+            }
+            AppAction::SetSearchTree(search_tree) => {
+                next.search_tree = search_tree;
+            }
+            AppAction::SetExplanation(explanation) => {
+                next.explanation = explanation;
+            }
+            AppAction::SetHint(hint) => {
+                next.hint = hint;
+            }
+            AppAction::ResumeSearch => {
+                next.search_generation = next.search_generation.wrapping_add(1);
+            }
+            AppAction::SetWinEstimate(index, estimate) => {
+                next.win_estimates.insert(index, estimate);
+            }
+            // The review search would replace the evaluation of the starting characters.
+            AppAction::StartReview if next.pending_start.is_some() => {}
+            AppAction::StartReview => {
+                next.review = Some(Rc::new(Review::new(&next)));
+            }
+            AppAction::ReviewResult(index, progress) => {
+                if let Some(mut review) = next.review.take() {
+                    Rc::make_mut(&mut review).add_result(&next, index, &progress);
+                    next.review = Some(review);
+                }
+            }
+            AppAction::SkipReview(index) => {
+                if let Some(review) = &mut next.review {
+                    Rc::make_mut(review).skip(index);
+                }
+            }
+            AppAction::SelectMove(index) => {
+                next.replay_index = index.filter(|&i| i < next.move_log.len());
+            }
+            AppAction::Redraw(selected) => {
+                if let Some(pending) = next.pending_redraw.take() {
+                    if let Some(player_id) = pending.current() {
+                        next.redraw(player_id, &selected);
+                    }
+                    next.pending_redraw = pending.next();
+                }
+            }
+            AppAction::KeepHand => {
+                if let Some(pending) = next.pending_redraw.take() {
+                    next.pending_redraw = pending.next();
+                }
+            }
+            AppAction::Reroll(selected) => {
+                let seed = next.seed ^ next.game_state.zobrist_hash();
+                if let Some(pending) = &mut next.pending_reroll {
+                    if let Some((player_id, _)) = pending.current() {
+                        pending.reroll(&next.game_state, player_id, &selected, seed);
+                        pending.use_chance();
+                    }
+                }
+                next.finish_reroll();
+            }
+            AppAction::KeepDice => {
+                if let Some(pending) = &mut next.pending_reroll {
+                    pending.keep();
+                }
+                next.finish_reroll();
             }
             AppAction::SetMessage(message) => {
                 next.message = message;
             }
             AppAction::PerformAction(action) => 'a: {
-                if action.player().is_none() {
+                if action.player().is_none() || next.is_setup_pending() {
                     break 'a;
                 }
                 let mut game_state: G = self.game_state.clone().deref().clone();
-                if let Err(e) = game_state.advance(action) {
-                    println!("reduce: Error: {e:?}")
-                } else {
-                    next.game_state = game_state.into();
+                let mut nondet = vec![];
+                let held =
+                    match advance_until(&mut game_state, action, &mut nondet, is_roll_request) {
+                        Ok(held) => held,
+                        Err(e) => {
+                            println!("reduce: Error: {e:?}");
+                            break 'a;
+                        }
+                    };
+                let prev = std::mem::replace(&mut next.game_state, game_state.into());
+                next.move_log.push(MoveLogEntry {
+                    game_state: prev.clone(),
+                    action,
+                    nondet,
+                    edits: vec![],
+                });
+                next.push_undo(prev);
+                next.redo_stack.clear();
+                // Estimates for later positions belong to the line that was undone.
+                let moves = next.move_log.len();
+                next.win_estimates.retain(|&i, _| i < moves);
+                if let Some((request, result)) = held {
+                    next.start_reroll(request, result);
+                }
+            }
+            // The previous game can't be changed while a new one is being set up.
+            AppAction::Undo | AppAction::Redo if next.pending_start.is_some() => {}
+            AppAction::Undo => {
+                next.review = None;
+                next.replay_index = None;
+                if next.can_undo() {
+                    // A position waiting on the roll can't be redone, so the roll is given to
+                    // the simulator as it is.
+                    if let Some(pending) = &mut next.pending_reroll {
+                        pending.players.clear();
+                    }
+                    next.finish_reroll();
+                    next.cancel_setup();
+                }
+                while let Some(prev) = next.undo_stack.pop_back() {
+                    let Some(entry) = next.move_log.pop() else {
+                        break;
+                    };
+                    let cur = std::mem::replace(&mut next.game_state, prev);
+                    next.redo_stack.push((cur, entry));
+                    if !next.is_skipped_by_undo(&next.game_state) {
+                        break;
+                    }
+                }
+            }
+            AppAction::Redo => {
+                next.review = None;
+                if next.can_redo() {
+                    next.cancel_setup();
+                }
+                while let Some((redo, entry)) = next.redo_stack.pop() {
+                    let cur = std::mem::replace(&mut next.game_state, redo);
+                    next.move_log.push(entry);
+                    next.push_undo(cur);
+                    if !next.is_skipped_by_undo(&next.game_state) {
+                        break;
+                    }
                 }
             }
         };
@@ -102,8 +683,12 @@ impl PartialEq for GameStateProp {
     }
 }
 
-fn default_game_state() -> G {
-    let rand1 = SmallRng::seed_from_u64(100);
+pub fn new_game((decklist1, decklist2): &(Decklist, Decklist), seed: u64) -> G {
+    let rng = SmallRng::seed_from_u64(seed);
+    new_standard_game((decklist1, decklist2).into(), rng)
+}
+
+pub fn default_decklists() -> (Decklist, Decklist) {
     let decklist1 = Decklist::new(
         smallvec![CharId::Yoimiya, CharId::Xingqiu, CharId::KamisatoAyaka],
         sample_deck(),
@@ -112,62 +697,186 @@ fn default_game_state() -> G {
         smallvec![CharId::Mona, CharId::Fischl, CharId::Collei],
         sample_deck(),
     );
-    new_standard_game((&decklist1, &decklist2).into(), rand1)
+    (decklist1, decklist2)
 }
 
+const DEFAULT_RANDOM_SEED: u64 = 100;
 const RANDOM_SEED_KEY: &str = "random_seed";
-const SEARCH_STEPS_KEY: &str = "search_steps";
+const CONTROLLERS_KEY: &str = "controllers";
+const PONDER_KEY: &str = "ponder";
 
 #[function_component(App)]
 pub fn app() -> Html {
     let app = use_reducer(AppState::default);
     let player_to_move = app.game_state.to_move();
     let hash = app.game_state.zobrist_hash();
-    let search_callback: Rc<RefCell<Option<Callback<SearchReturn>>>> = use_mut_ref(|| None);
+    let search_callback: Rc<RefCell<Option<Callback<(usize, SearchReturn)>>>> =
+        use_mut_ref(|| None);
 
     let handler = {
-        //let app = app.clone();
         let search_callback = search_callback.clone();
         use_mut_ref(move || {
-            let handler = {
-                SearchWorker::bridge(Rc::new(move |msg| {
-                    if let Ok(c) = search_callback.try_borrow() {
-                        c.iter().for_each(|c| c.emit(msg.clone()))
-                    }
-                }))
-            };
-            handler
+            SearchBridge::new(Rc::new(move |msg| {
+                if let Ok(c) = search_callback.try_borrow() {
+                    c.iter().for_each(|c| c.emit(msg.clone()))
+                }
+            }))
         })
     };
 
     {
         let app = app.clone();
         let handler = handler.clone();
-        *search_callback.try_borrow_mut().unwrap() = Some(Callback::from(move |msg| match msg {
-            SearchReturn(false, Some(res), total_time_ns) => {
-                app.dispatch(AppAction::SetMessage(format!(
-                    "Step {}, {} states visited",
-                    res.counter.summary(total_time_ns),
-                    res.counter.states_visited
-                )));
-                handler.try_borrow_mut().unwrap().send(SearchAction::Step);
-            }
-            SearchReturn(true, Some(res), total_time_ns) => {
-                gloo::console::log!("Finish");
-                app.dispatch(AppAction::SetMessage(format!(
-                    "Finished {}, {} states visited, Best Move = {}",
-                    res.counter.summary(total_time_ns),
-                    res.counter.states_visited,
-                    res.pv
-                        .head()
-                        .map(|a| describe_action(&app.game_state, a))
-                        .unwrap_or_default()
-                )));
-                let Some(head) = res.pv.head() else { return };
-                app.dispatch(AppAction::PerformAction(head));
-            }
-            SearchReturn(_, None, _) => {
-                handler.try_borrow_mut().unwrap().send(SearchAction::Step);
+        *search_callback.try_borrow_mut().unwrap() = Some(Callback::from(move |(worker, msg)| {
+            let mut bridge: RefMut<SearchBridge> = handler.as_ref().borrow_mut();
+            let Some((purpose, msg)) = bridge.receive(worker, msg) else {
+                return;
+            };
+            let worker_summaries = |progress: &SearchProgress| -> String {
+                progress
+                    .worker_summaries
+                    .iter()
+                    .map(|s| format!("\n{s}"))
+                    .collect()
+            };
+            match msg {
+                SearchReturn::Started { .. } => {}
+                SearchReturn::Progress { progress, .. } => match purpose {
+                    SearchPurpose::Analysis => {
+                        let analysis = Analysis::new(&progress, false);
+                        app.dispatch(AppAction::SetAnalysis(Some(Rc::new(analysis))));
+                    }
+                    SearchPurpose::Move => {
+                        app.dispatch(AppAction::SetMessage(format!(
+                            "Step {}/{}: {}, {} states visited{}",
+                            progress.step,
+                            progress.total_steps,
+                            progress.result.counter.summary(progress.total_time_ns),
+                            progress.result.counter.states_visited,
+                            worker_summaries(&progress)
+                        )));
+                    }
+                    SearchPurpose::Ponder
+                    | SearchPurpose::Hint
+                    | SearchPurpose::Review(..)
+                    | SearchPurpose::StartingCharacter(..) => {}
+                },
+                SearchReturn::Finished {
+                    best,
+                    progress,
+                    search_tree,
+                    ..
+                } => {
+                    gloo::console::log!("Finish");
+                    let position = match purpose {
+                        SearchPurpose::Move | SearchPurpose::Analysis => {
+                            Some((app.move_log.len(), app.game_state.clone()))
+                        }
+                        SearchPurpose::Review(index) => app
+                            .move_log
+                            .get(index)
+                            .map(|entry| (index, entry.game_state.clone())),
+                        SearchPurpose::Ponder
+                        | SearchPurpose::Hint
+                        | SearchPurpose::StartingCharacter(..) => None,
+                    };
+                    if let Some((index, game_state)) = position {
+                        let estimate = game_state
+                            .to_move()
+                            .and_then(|player_id| win_estimate(player_id, &progress));
+                        if let Some(estimate) = estimate {
+                            app.dispatch(AppAction::SetWinEstimate(index, estimate));
+                        }
+                    }
+                    match purpose {
+                        SearchPurpose::Analysis => {
+                            app.dispatch(AppAction::SetSearchTree(search_tree));
+                            let analysis = Analysis::new(&progress, true);
+                            app.dispatch(AppAction::SetAnalysis(Some(Rc::new(analysis))));
+                        }
+                        SearchPurpose::Move => {
+                            app.dispatch(AppAction::SetSearchTree(search_tree));
+                            let res = &progress.result;
+                            let pv_head = res.pv.head();
+                            let explanation = best.player().map(|player_id| {
+                                Rc::new(MoveExplanation::new(
+                                    &app.game_state,
+                                    player_id,
+                                    best,
+                                    &progress,
+                                    app.explanation.as_deref(),
+                                ))
+                            });
+                            app.dispatch(AppAction::SetMessage(format!(
+                                "{}Finished {}, {} states visited, Best Move = {}{}{}",
+                                explanation
+                                    .as_ref()
+                                    .map(|e| format!("{e}\n"))
+                                    .unwrap_or_default(),
+                                res.counter.summary(progress.total_time_ns),
+                                res.counter.states_visited,
+                                pv_head
+                                    .map(|a| describe_action(&app.game_state, a))
+                                    .unwrap_or_default(),
+                                if pv_head != Some(best) {
+                                    format!(", Played = {}", describe_action(&app.game_state, best))
+                                } else {
+                                    String::new()
+                                },
+                                worker_summaries(&progress)
+                            )));
+                            app.dispatch(AppAction::SetExplanation(explanation));
+                            app.dispatch(AppAction::PerformAction(best));
+                        }
+                        SearchPurpose::Hint => {
+                            let hint = Hint::new(&progress);
+                            app.dispatch(AppAction::SetMessage(format!(
+                                "Hint: {}",
+                                hint.best()
+                                    .map(|a| describe_action(&app.game_state, a))
+                                    .unwrap_or_default()
+                            )));
+                            app.dispatch(AppAction::SetHint(Some(Rc::new(hint))));
+                            // The hint replaced any analysis or pondering of the position.
+                            app.dispatch(AppAction::ResumeSearch);
+                        }
+                        SearchPurpose::Review(index) => {
+                            app.dispatch(AppAction::ReviewResult(index, progress));
+                        }
+                        SearchPurpose::StartingCharacter(player_id, index) => {
+                            app.dispatch(AppAction::StartingCharacterEvaluated(
+                                player_id,
+                                index,
+                                best_value(&progress.action_stats),
+                            ));
+                        }
+                        // The tree is kept in the worker for the reply after the human moves.
+                        SearchPurpose::Ponder => {}
+                    }
+                }
+                SearchReturn::Abandoned { .. } => match purpose {
+                    SearchPurpose::Review(index) => app.dispatch(AppAction::SkipReview(index)),
+                    // Unless the same candidate is already searched again, e.g. for a new game.
+                    SearchPurpose::StartingCharacter(player_id, index)
+                        if bridge.active_purpose() != Some(purpose) =>
+                    {
+                        app.dispatch(AppAction::StartingCharacterAbandoned(player_id, index));
+                    }
+                    _ => {}
+                },
+                SearchReturn::Error { message, .. } => {
+                    match purpose {
+                        SearchPurpose::StartingCharacter(player_id, index) => {
+                            app.dispatch(AppAction::StartingCharacterEvaluated(
+                                player_id, index, None,
+                            ));
+                        }
+                        SearchPurpose::Hint => app.dispatch(AppAction::ResumeSearch),
+                        SearchPurpose::Review(index) => app.dispatch(AppAction::SkipReview(index)),
+                        _ => {}
+                    }
+                    app.dispatch(AppAction::SetMessage(format!("Search error: {message}")));
+                }
             }
         }));
     }
@@ -176,60 +885,456 @@ pub fn app() -> Html {
         let handler = handler.clone();
         let app = app.clone();
         use_effect_with_deps(
-            move |(_, player_to_move)| {
-                if let Some(player_id) = *player_to_move {
-                    if player_id == PlayerId::PlayerSecond {
-                        let mut r: RefMut<Box<_>> = handler.as_ref().borrow_mut();
-                        let mut gsr = app.game_state.clone();
-                        {
-                            let game_state = Rc::make_mut(&mut gsr);
-                            game_state.hide_private_information(PlayerId::PlayerFirst);
-                        }
-                        r.send(SearchAction::Start {
-                            maximize_player: PlayerId::PlayerSecond,
-                            game_state: gsr,
-                            steps: LocalStorage::get(SEARCH_STEPS_KEY).unwrap_or(5),
-                        });
-                        app.dispatch(AppAction::SetMessage("Searching...".to_string()));
+            move |(_, player_to_move, controllers, analyze, ponder, _, setup_pending)| {
+                let mut bridge: RefMut<SearchBridge> = handler.as_ref().borrow_mut();
+                // Other searches, such as a post-game review, are left running.
+                let Some(player_id) = player_to_move.filter(|_| !*setup_pending) else {
+                    bridge.abandon_if(SearchPurpose::follows_position);
+                    return;
+                };
+                let search = match controllers.difficulty(player_id) {
+                    Some(difficulty) => {
+                        let settings = difficulty.search_settings(&app.search_settings);
+                        let stop = settings.stop_condition();
+                        Some((
+                            SearchPurpose::Move,
+                            player_id,
+                            settings,
+                            stop,
+                            difficulty.move_selection(),
+                        ))
                     }
+                    None if *analyze => Some((
+                        SearchPurpose::Analysis,
+                        player_id,
+                        app.search_settings.clone(),
+                        app.search_settings.stop_condition(),
+                        MoveSelection::Best,
+                    )),
+                    // Search on behalf of the AI opponent, so that its reply can reuse the tree.
+                    None if *ponder => {
+                        let ai_player = opponent(player_id);
+                        controllers.difficulty(ai_player).map(|difficulty| {
+                            (
+                                SearchPurpose::Ponder,
+                                ai_player,
+                                difficulty.search_settings(&app.search_settings),
+                                PONDER_STOP_CONDITION,
+                                MoveSelection::Best,
+                            )
+                        })
+                    }
+                    None => None,
+                };
+                let Some((purpose, maximize_player, settings, stop, move_selection)) = search
+                else {
+                    bridge.abandon_if(SearchPurpose::follows_position);
+                    return;
+                };
+                let determinization = (settings.determinizations > 1).then(|| {
+                    Determinization::new(&app, opponent(maximize_player), settings.determinizations)
+                });
+                bridge.start(
+                    purpose,
+                    &settings,
+                    stop,
+                    maximize_player,
+                    &app.game_state,
+                    move_selection,
+                    determinization,
+                );
+                if purpose == SearchPurpose::Move {
+                    app.dispatch(AppAction::SetMessage("Searching...".to_string()));
                 }
             },
-            (hash, player_to_move),
+            (
+                hash,
+                player_to_move,
+                app.controllers,
+                app.analyze,
+                app.ponder,
+                app.search_generation,
+                app.is_setup_pending(),
+            ),
         );
     }
 
-    let on_start = use_callback(
-        move |r: Rc<(Decklist, Decklist)>, app| {
-            let (decklist1, decklist2) = r.as_ref();
-            let rng = SmallRng::seed_from_u64(LocalStorage::get(RANDOM_SEED_KEY).unwrap_or(100));
-            app.dispatch(AppAction::SetGameState(
-                new_standard_game((decklist1, decklist2).into(), rng).into(),
-            ));
-        },
-        app.clone(),
-    );
+    {
+        let handler = handler.clone();
+        use_effect_with_deps(
+            move |search_settings| {
+                handler.as_ref().borrow_mut().set_config(search_settings);
+            },
+            app.search_settings.clone(),
+        );
+    }
+
+    {
+        let handler = handler.clone();
+        let app = app.clone();
+        use_effect_with_deps(
+            move |_| {
+                let Some(index) = app.review.as_ref().and_then(|r| r.next_position()) else {
+                    return;
+                };
+                let Some(entry) = app.move_log.get(index) else {
+                    return;
+                };
+                let Some(player_id) = entry.action.player() else {
+                    return;
+                };
+                handler.as_ref().borrow_mut().start(
+                    SearchPurpose::Review(index),
+                    &app.search_settings,
+                    REVIEW_STOP_CONDITION,
+                    player_id,
+                    &entry.game_state,
+                    MoveSelection::Best,
+                    None,
+                );
+            },
+            app.review.as_ref().map(|r| r.reviewed),
+        );
+    }
+
+    {
+        let handler = handler.clone();
+        let app = app.clone();
+        use_effect_with_deps(
+            move |(next_evaluation, _)| {
+                let (Some(pending), Some((player_id, index))) =
+                    (&app.pending_start, *next_evaluation)
+                else {
+                    return;
+                };
+                let Some(difficulty) = app.controllers.difficulty(player_id) else {
+                    return;
+                };
+                handler.as_ref().borrow_mut().start(
+                    SearchPurpose::StartingCharacter(player_id, index),
+                    &difficulty.search_settings(&app.search_settings),
+                    STARTING_CHARACTER_STOP_CONDITION,
+                    player_id,
+                    &Rc::new(pending.candidate_game(player_id, index)),
+                    MoveSelection::Best,
+                    None,
+                );
+            },
+            (
+                app.pending_start
+                    .as_ref()
+                    .and_then(|pending| pending.next_evaluation()),
+                app.pending_start
+                    .as_ref()
+                    .map(|pending| pending.evaluation_attempt),
+            ),
+        );
+    }
+
+    let on_undo = {
+        let app = app.clone();
+        let handler = handler.clone();
+        Callback::from(move |()| {
+            if app.pending_start.is_some() {
+                return;
+            }
+            handler.borrow_mut().abandon();
+            app.dispatch(AppAction::Undo);
+        })
+    };
+    let on_redo = {
+        let app = app.clone();
+        let handler = handler.clone();
+        Callback::from(move |()| {
+            if app.pending_start.is_some() {
+                return;
+            }
+            handler.borrow_mut().abandon();
+            app.dispatch(AppAction::Redo);
+        })
+    };
+
+    {
+        let on_undo = on_undo.clone();
+        let on_redo = on_redo.clone();
+        use_effect_with_deps(
+            move |_| {
+                let listener = EventListener::new(&gloo::utils::document(), "keydown", move |e| {
+                    let Some(e) = e.dyn_ref::<KeyboardEvent>() else {
+                        return;
+                    };
+                    if !(e.ctrl_key() || e.meta_key()) {
+                        return;
+                    }
+                    let in_text_input = e
+                        .target()
+                        .map(|t| t.has_type::<web_sys::HtmlInputElement>())
+                        .unwrap_or_default();
+                    if in_text_input {
+                        return;
+                    }
+                    match e.key().to_lowercase().as_str() {
+                        "z" if e.shift_key() => on_redo.emit(()),
+                        "z" => on_undo.emit(()),
+                        "y" => on_redo.emit(()),
+                        _ => return,
+                    }
+                    e.prevent_default();
+                });
+                move || drop(listener)
+            },
+            (),
+        );
+    }
+
+    let on_set_controller = {
+        let app = app.clone();
+        Callback::from(move |(player_id, controller)| {
+            app.dispatch(AppAction::SetController(player_id, controller))
+        })
+    };
 
-    let active_player = app.game_state.game_state.active_player();
-    let to_move = app.game_state.to_move();
-    let dice = active_player.map(|p| p.dice_counter());
+    let on_toggle_analyze = {
+        let app = app.clone();
+        Callback::from(move |_: MouseEvent| app.dispatch(AppAction::SetAnalyze(!app.analyze)))
+    };
+
+    let on_toggle_ponder = {
+        let app = app.clone();
+        Callback::from(move |_: MouseEvent| app.dispatch(AppAction::SetPonder(!app.ponder)))
+    };
+
+    let on_hint = {
+        let app = app.clone();
+        let handler = handler.clone();
+        Callback::from(move |_: MouseEvent| {
+            let Some(player_id) = app.game_state.to_move() else {
+                return;
+            };
+            handler.as_ref().borrow_mut().start(
+                SearchPurpose::Hint,
+                &app.search_settings,
+                HINT_STOP_CONDITION,
+                player_id,
+                &app.game_state,
+                MoveSelection::Best,
+                None,
+            );
+            app.dispatch(AppAction::SetMessage("Looking for a hint...".to_string()));
+        })
+    };
+
+    let on_set_search_settings = {
+        let app = app.clone();
+        Callback::from(move |search_settings| {
+            app.dispatch(AppAction::SetSearchSettings(search_settings))
+        })
+    };
+
+    let on_start = {
+        let handler = handler.clone();
+        use_callback(
+            move |r: Rc<(Decklist, Decklist)>, app| {
+                handler.as_ref().borrow_mut().abandon();
+                let seed = LocalStorage::get(RANDOM_SEED_KEY).unwrap_or(DEFAULT_RANDOM_SEED);
+                app.dispatch(AppAction::PrepareGame(r, seed));
+            },
+            app.clone(),
+        )
+    };
+
+    let replay_index = app.replay_index;
+    let on_select_replay = {
+        let app = app.clone();
+        Callback::from(move |i| app.dispatch(AppAction::SelectMove(i)))
+    };
+    let shown_game_state = replay_index
+        .and_then(|i| app.move_log.get(i))
+        .map(|entry| entry.game_state.clone())
+        .unwrap_or_else(|| app.game_state.clone());
+
+    let human_to_move = app.is_human_to_move(&app.game_state) && !app.is_setup_pending();
+    let pending_reroll = app
+        .pending_reroll
+        .as_ref()
+        .and_then(|pending| pending.current());
+    // While the AI is to move, keep showing the dice of the only human seat, so that the result
+    // of the roll phase is visible.
+    let dice_player = if let Some((player_id, _)) = pending_reroll {
+        Some(player_id)
+    } else if human_to_move {
+        app.game_state.to_move()
+    } else {
+        [PlayerId::PlayerFirst, PlayerId::PlayerSecond]
+            .into_iter()
+            .find(|&p| !app.controllers.is_ai(p) && app.controllers.is_ai(opponent(p)))
+    };
+    // The dice of a held roll are not given to the simulator yet.
+    let dice = dice_player.map(|p| match &app.pending_reroll {
+        Some(pending) => pending.dice(p),
+        None => app.game_state.game_state.player(p).dice_counter(),
+    });
+    // Moves can be made from the board only on the current position.
+    let board_actions: Vec<Input> = if human_to_move && replay_index.is_none() {
+        app.game_state.actions().into_iter().collect()
+    } else {
+        vec![]
+    };
+    let on_board_action = {
+        let app = app.clone();
+        Callback::from(move |action| app.dispatch(AppAction::PerformAction(action)))
+    };
+    // Dropping a hand card onto the dice performs Elemental Tuning.
+    let on_dice_drop = {
+        let app = app.clone();
+        let board_actions = board_actions.clone();
+        Callback::from(move |e: DragEvent| {
+            e.prevent_default();
+            let Some(player_id) = app.game_state.to_move() else {
+                return;
+            };
+            let Some(index) = e
+                .data_transfer()
+                .and_then(|data_transfer| data_transfer.get_data(HAND_CARD_DRAG_FORMAT).ok())
+                .and_then(|data| data.parse::<usize>().ok())
+            else {
+                return;
+            };
+            let hand = app.game_state.game_state.player(player_id).hand();
+            let Some(&card_id) = hand.iter().nth(index) else {
+                return;
+            };
+            let action = Input::FromPlayer(player_id, PlayerAction::ElementalTuning(card_id));
+            if board_actions.contains(&action) {
+                app.dispatch(AppAction::PerformAction(action));
+            }
+        })
+    };
+    let on_dice_drag_over = Callback::from(|e: DragEvent| e.prevent_default());
+    let reroll_selection = use_state(Vec::<usize>::new);
+    let on_toggle_dice = pending_reroll.map(|_| {
+        let reroll_selection = reroll_selection.clone();
+        Callback::from(move |index: usize| {
+            let mut selection = (*reroll_selection).clone();
+            match selection.iter().position(|&i| i == index) {
+                Some(pos) => {
+                    selection.remove(pos);
+                }
+                None => selection.push(index),
+            }
+            reroll_selection.set(selection);
+        })
+    });
+    {
+        // The selection is for the dice of the current seat and reroll only.
+        let reroll_selection = reroll_selection.clone();
+        use_effect_with_deps(move |_| reroll_selection.set(vec![]), pending_reroll);
+    }
+    let on_reroll = {
+        let app = app.clone();
+        let reroll_selection = reroll_selection.clone();
+        Callback::from(move |()| app.dispatch(AppAction::Reroll((*reroll_selection).clone())))
+    };
+    let on_keep_dice = {
+        let app = app.clone();
+        Callback::from(move |()| app.dispatch(AppAction::KeepDice))
+    };
     html! {
         <main>
             <h1>{ "GITCGSim Web" }</h1>
             <div class="col">
-                <Board game_state={app.game_state.clone()} hash={app.game_state.zobrist_hash()} />
+                <StartingCharacterSelection app={app.clone()} />
+                <RedrawSelection app={app.clone()} />
+                <Board
+                    game_state={shown_game_state.clone()}
+                    hash={shown_game_state.zobrist_hash()}
+                    controllers={app.controllers}
+                    actions={board_actions}
+                    on_action={on_board_action}
+                />
+                <WinProbabilityGraph app={app.clone()} />
                 <div class="moves-list">
                     <h2>{"Dice"}</h2>
-                    {if to_move == Some(PlayerId::PlayerFirst) {
-                        html! { for dice.map(|dice| html! { <DiceList {dice} /> }) }
+                    <div
+                        class="dice-drop"
+                        title="Drop a card here for Elemental Tuning"
+                        ondragover={on_dice_drag_over}
+                        ondrop={on_dice_drop}
+                    >
+                        {match dice {
+                            Some(dice) => html! {
+                                <DiceList
+                                    {dice}
+                                    selected={(*reroll_selection).clone()}
+                                    on_toggle={on_toggle_dice}
+                                />
+                            },
+                            None => html! { " - " },
+                        }}
+                    </div>
+                    {for pending_reroll.map(|(player_id, chances)| html! {
+                        <RerollControls
+                            {player_id}
+                            {chances}
+                            selected={reroll_selection.len()}
+                            {on_reroll}
+                            on_keep={on_keep_dice}
+                        />
+                    })}
+                    <h2>{"Actions"}</h2>
+                    <div class="undo-redo">
+                        <button
+                            onclick={on_undo.reform(|_| ())}
+                            disabled={!app.can_undo() || app.pending_start.is_some()}
+                            title="Undo (Ctrl+Z)"
+                        >{"Undo"}</button>
+                        <button
+                            onclick={on_redo.reform(|_| ())}
+                            disabled={!app.can_redo() || app.pending_start.is_some()}
+                            title="Redo (Ctrl+Y)"
+                        >{"Redo"}</button>
+                        <button
+                            class={classes!("analyze-toggle", if app.analyze { Some("is-active") } else { None })}
+                            onclick={on_toggle_analyze}
+                            title="Search the position without playing a move"
+                        >{"Analyze"}</button>
+                        <button
+                            class={classes!("analyze-toggle", if app.ponder { Some("is-active") } else { None })}
+                            onclick={on_toggle_ponder}
+                            title="Let the AI think during your turn"
+                        >{"Ponder"}</button>
+                        <button
+                            onclick={on_hint}
+                            disabled={!human_to_move}
+                            title="Search briefly and highlight the recommended move"
+                        >{"Hint"}</button>
+                    </div>
+                    {if human_to_move {
+                        html! {
+                            <>
+                                {if app.analyze {
+                                    html! { <AnalysisPanel app={app.clone()} /> }
+                                } else {
+                                    html! {}
+                                }}
+                                <ActionsList app={app.clone()} />
+                            </>
+                        }
                     } else {
                         html! { " - " }
                     }}
-                    <h2>{"Actions"}</h2>
-                    {if to_move == Some(PlayerId::PlayerFirst) {
-                        html! { <ActionsList app={app.clone()} /> }
+                    {if app.game_state.winner().is_some() {
+                        html! {
+                            <>
+                                <h2>{"Review"}</h2>
+                                <ReviewPanel app={app.clone()} on_select={on_select_replay.clone()} />
+                            </>
+                        }
                     } else {
-                        html! { " - " }
+                        html! {}
                     }}
+                    <h2>{"Move Log"}</h2>
+                    <MoveLog app={app.clone()} selected={replay_index} on_select={on_select_replay} />
                 </div>
             </div>
             <div>
@@ -237,9 +1342,23 @@ pub fn app() -> Html {
                     {&app.message}
                 </pre>
             </div>
+            {for app.search_tree.clone().map(|node| html! {
+                <details class="search-tree-panel">
+                    <summary>{"Search Tree"}</summary>
+                    <SearchTree {node} expanded={true} />
+                </details>
+            })}
             <hr />
             <div class="col">
-                <StartGameForm {on_start} />
+                <div>
+                    <StartGameForm {on_start} />
+                    <ControllersForm controllers={app.controllers} on_change={on_set_controller} />
+                </div>
+                <ReplayControls app={app.clone()} />
+                <SearchSettingsForm
+                    settings={app.search_settings.clone()}
+                    on_change={on_set_search_settings}
+                />
                 <DeckEditor />
             </div>
         </main>
@@ -249,28 +1368,85 @@ pub fn app() -> Html {
 #[derive(Properties, PartialEq)]
 struct DiceListProps {
     dice: DiceCounter,
+    /// Indices of the selected dice.
+    #[prop_or_default]
+    selected: Vec<usize>,
+    /// Called with the index of a clicked dice, when the dice can be selected.
+    #[prop_or_default]
+    on_toggle: Option<Callback<usize>>,
 }
 
 #[function_component(DiceList)]
 fn dice_list(props: &DiceListProps) -> Html {
     html! {
         <div class="dice-list">
-            {for props.dice.tally().iter().copied().flat_map(|(d, c)| {
-                (0..c).into_iter().map(move |_|
-                    match d {
-                        Dice::Omni => html! { <span class="dice dice-omni" title={"Omni"}>{"O"}</span> },
-                        Dice::Elem(e) => html! {
-                            <span class={format!("dice dice-elem elem-{e:?}")} title={e.name()}>
-                                {e.name().chars().next()}
-                            </span>
-                        }
-                    }
-                )
+            {for dice_vec(&props.dice).into_iter().enumerate().map(|(i, d)| {
+                let (kind, title, label) = match d {
+                    Dice::Omni => ("dice-omni".to_string(), "Omni", 'O'),
+                    Dice::Elem(e) => (
+                        format!("dice-elem elem-{e:?}"),
+                        e.name(),
+                        e.name().chars().next().unwrap_or('?'),
+                    ),
+                };
+                let class = classes!(
+                    "dice",
+                    kind,
+                    props.on_toggle.is_some().then_some("is-selectable"),
+                    props.selected.contains(&i).then_some("is-selected"),
+                );
+                let onclick = props.on_toggle.as_ref().map(|on_toggle| on_toggle.reform(move |_: MouseEvent| i));
+                html! { <span {class} {title} {onclick}>{label}</span> }
             })}
         </div>
     }
 }
 
+#[derive(Properties, PartialEq)]
+struct ControllersFormProps {
+    controllers: Controllers,
+    on_change: Callback<(PlayerId, Controller)>,
+}
+
+#[function_component(ControllersForm)]
+fn controllers_form(props: &ControllersFormProps) -> Html {
+    let selector = |player_id: PlayerId| {
+        let id = format!("controller-{player_id}");
+        let selected = props.controllers.get(player_id);
+        let onchange = props.on_change.reform(move |e: Event| {
+            let controller = e
+                .target()
+                .and_then(|t| t.dyn_into::<web_sys::HtmlSelectElement>().ok())
+                .and_then(|input| {
+                    Controller::ALL
+                        .into_iter()
+                        .find(|c| c.name() == input.value())
+                })
+                .unwrap_or(selected);
+            (player_id, controller)
+        });
+        html! {
+            <div>
+                <label for={id.clone()}>
+                    {format!("Player {player_id}: ")}
+                    <select id={id.clone()} name={id} {onchange}>
+                        {for Controller::ALL.into_iter().map(|c| html! {
+                            <option value={c.name()} selected={c == selected}>{c.name()}</option>
+                        })}
+                    </select>
+                </label>
+            </div>
+        }
+    };
+    html! {
+        <div class="controllers-form">
+            <h2>{"Players"}</h2>
+            {selector(PlayerId::PlayerFirst)}
+            {selector(PlayerId::PlayerSecond)}
+        </div>
+    }
+}
+
 #[derive(Properties, PartialEq)]
 struct StartGameFormProps {
     on_start: Callback<Rc<(Decklist, Decklist)>>,
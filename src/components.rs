@@ -1,18 +1,33 @@
 use std::rc::Rc;
 
-use crate::app;
+use crate::{
+    actions_list::{ActionTarget, CostInfo},
+    app::{self, Controllers, GameStateProp},
+    tooltip::{describe_dmg, CardDetail, SkillDetail, StatusDetail, Tooltip},
+};
 use gitcg_sim::prelude::{card_defs::Status, tcg_model::*, *};
 use yew::prelude::*;
 
+/// Drag data format of a hand card, holding the index of the card in the hand.
+pub const HAND_CARD_DRAG_FORMAT: &str = "application/x-gitcg-hand-card";
+
 #[derive(Properties)]
 pub struct BoardProps {
     pub game_state: Rc<app::G>,
     pub hash: u64,
+    pub controllers: Controllers,
+    /// Legal inputs that can be performed by clicking on the board.
+    #[prop_or_default]
+    pub actions: Vec<Input>,
+    #[prop_or_default]
+    pub on_action: Callback<Input>,
 }
 
 impl PartialEq for BoardProps {
     fn eq(&self, other: &Self) -> bool {
         self.hash == other.hash
+            && self.controllers == other.controllers
+            && self.actions == other.actions
     }
 }
 
@@ -22,29 +37,99 @@ pub fn board(props: &BoardProps) -> Html {
     let count_p1 = props.game_state.nd.state.decks.0.count;
     let count_p2 = props.game_state.nd.state.decks.1.count;
     let hash = game_state.zobrist_hash();
+    let hidden_p1 = props.controllers.is_hand_hidden(PlayerId::PlayerFirst);
+    let hidden_p2 = props.controllers.is_hand_hidden(PlayerId::PlayerSecond);
+    // Card waiting for a target to be chosen.
+    let selected_card = use_state(|| None::<CardId>);
+    {
+        let selected_card = selected_card.clone();
+        use_effect_with_deps(move |_| selected_card.set(None), hash);
+    }
+    let on_play_card = {
+        let actions = props.actions.clone();
+        let on_action = props.on_action.clone();
+        let selected_card = selected_card.clone();
+        Callback::from(
+            move |card_id| match play_card_actions(&actions, card_id)[..] {
+                [] => {}
+                [action] => on_action.emit(action),
+                _ => selected_card.set(Some(card_id)),
+            },
+        )
+    };
+    let target_prompt = (*selected_card)
+        .map(|card_id| (card_id, play_card_actions(&props.actions, card_id)))
+        .filter(|(_, targets)| targets.len() > 1)
+        .map(|(card_id, targets)| {
+            let targets_game_state = GameStateProp::new(game_state);
+            let on_cancel = {
+                let selected_card = selected_card.clone();
+                Callback::from(move |_: MouseEvent| selected_card.set(None))
+            };
+            html! {
+                <div class="card-targets">
+                    <h4>{format!("Choose a target for {}", card_id.card().name)}</h4>
+                    {for targets.into_iter().map(|action| {
+                        let onclick = {
+                            let on_action = props.on_action.clone();
+                            let selected_card = selected_card.clone();
+                            Callback::from(move |_: MouseEvent| {
+                                selected_card.set(None);
+                                on_action.emit(action);
+                            })
+                        };
+                        html! {
+                            <button {onclick}>
+                                <ActionTarget {action} game_state={targets_game_state.clone()} />
+                            </button>
+                        }
+                    })}
+                    <button onclick={on_cancel}>{"Cancel"}</button>
+                </div>
+            }
+        });
     html! {
         <div class="board">
             <h2>{"Board"}</h2>
-            <PlayerPart player_state={*game_state.player(PlayerId::PlayerSecond)} status_collection={game_state.status_collection(PlayerId::PlayerSecond).clone()} player_id={PlayerId::PlayerSecond} {hash} />
+            {for target_prompt}
+            <PlayerPart player_state={*game_state.player(PlayerId::PlayerSecond)} status_collection={game_state.status_collection(PlayerId::PlayerSecond).clone()} player_id={PlayerId::PlayerSecond} hidden={hidden_p2} {hash} actions={props.actions.clone()} on_action={props.on_action.clone()} on_play_card={on_play_card.clone()} />
             <PlayerDeck player_id={PlayerId::PlayerSecond} deck_count={count_p2} dice_count={game_state.player(PlayerId::PlayerSecond).dice_counter().total()} />
             <div class="divider" />
-            <PlayerPart player_state={*game_state.player(PlayerId::PlayerFirst)} status_collection={game_state.status_collection(PlayerId::PlayerFirst).clone()} player_id={PlayerId::PlayerFirst} {hash} />
+            <PlayerPart player_state={*game_state.player(PlayerId::PlayerFirst)} status_collection={game_state.status_collection(PlayerId::PlayerFirst).clone()} player_id={PlayerId::PlayerFirst} hidden={hidden_p1} {hash} actions={props.actions.clone()} on_action={props.on_action.clone()} {on_play_card} />
             <PlayerDeck player_id={PlayerId::PlayerFirst} deck_count={count_p1} dice_count={game_state.player(PlayerId::PlayerFirst).dice_counter().total()} />
         </div>
     }
 }
 
+/// The legal inputs playing `card_id`, one for each possible target.
+fn play_card_actions(actions: &[Input], card_id: CardId) -> Vec<Input> {
+    actions
+        .iter()
+        .copied()
+        .filter(|action| {
+            matches!(action, Input::FromPlayer(_, PlayerAction::PlayCard(c, _)) if *c == card_id)
+        })
+        .collect()
+}
+
 #[derive(Properties)]
 pub struct PlayerPartProps {
     pub player_state: PlayerState,
     pub status_collection: StatusCollection,
     pub player_id: PlayerId,
+    pub hidden: bool,
     pub hash: u64,
+    pub actions: Vec<Input>,
+    pub on_action: Callback<Input>,
+    pub on_play_card: Callback<CardId>,
 }
 
 impl PartialEq for PlayerPartProps {
     fn eq(&self, other: &Self) -> bool {
-        self.player_id == other.player_id && self.hash == other.hash
+        self.player_id == other.player_id
+            && self.hidden == other.hidden
+            && self.hash == other.hash
+            && self.actions == other.actions
     }
 }
 
@@ -76,13 +161,29 @@ pub fn player_part(props: &PlayerPartProps) -> Html {
         player_state,
         hash,
         status_collection,
-        ..
+        hidden,
+        actions,
+        on_action,
+        on_play_card,
+        player_id,
     } = props;
+    let player_id = *player_id;
     let chars = &player_state.char_states();
     let active = player_state.active_char_idx();
     let summons = status_collection.summon_statuses_vec();
     let supports = status_collection.support_statuses_vec();
-    let hidden = props.player_id == PlayerId::PlayerSecond;
+    let castable_skills: Vec<SkillId> = actions
+        .iter()
+        .filter_map(|action| match *action {
+            Input::FromPlayer(p, PlayerAction::CastSkill(skill_id)) if p == player_id => {
+                Some(skill_id)
+            }
+            _ => None,
+        })
+        .collect();
+    let on_cast_skill = on_action
+        .reform(move |skill_id| Input::FromPlayer(player_id, PlayerAction::CastSkill(skill_id)));
+    let is_legal = |act: PlayerAction| actions.contains(&Input::FromPlayer(player_id, act));
     html! {
         <div class={classes!("player-part", props.player_id.to_string())}>
             <h3>{format!("Player {}", props.player_id)}</h3>
@@ -110,6 +211,13 @@ pub fn player_part(props: &PlayerPartProps) -> Html {
                         status_collection.team_statuses_vec()
                             .iter().copied().copied().collect()
                     } else { vec![] };
+                    // Skills can only be cast by the active character.
+                    let castable_skills = if is_active { castable_skills.clone() } else { vec![] };
+                    let switch_action = [PlayerAction::SwitchCharacter(i as u8), PlayerAction::PostDeathSwitch(i as u8)]
+                        .into_iter()
+                        .find(|&act| is_legal(act))
+                        .map(|act| Input::FromPlayer(player_id, act));
+                    let on_switch = switch_action.map(|action| on_action.reform(move |_| action));
                     html! {
                         <Character
                             char_state={*c}
@@ -118,6 +226,9 @@ pub fn player_part(props: &PlayerPartProps) -> Html {
                             {char_statuses}
                             {team_statuses}
                             {hash}
+                            {castable_skills}
+                            on_cast_skill={on_cast_skill.clone()}
+                            {on_switch}
                         />
                     }
                 })}
@@ -131,8 +242,20 @@ pub fn player_part(props: &PlayerPartProps) -> Html {
                 </div>
             </div>
             <div class="player-hand">
-                {for player_state.hand().iter().copied().map(|card_id| html! {
-                    <Card {card_id} {hidden} />
+                {for player_state.hand().iter().copied().enumerate().map(|(i, card_id)| {
+                    let playable = actions.iter().any(|action| {
+                        matches!(action, Input::FromPlayer(p, PlayerAction::PlayCard(c, _)) if *p == player_id && *c == card_id)
+                    });
+                    let tunable = is_legal(PlayerAction::ElementalTuning(card_id));
+                    let on_click = on_play_card.reform(move |_| card_id);
+                    let on_drag_start = Callback::from(move |e: DragEvent| {
+                        if let Some(data_transfer) = e.data_transfer() {
+                            data_transfer.set_data(HAND_CARD_DRAG_FORMAT, &i.to_string()).ok();
+                        }
+                    });
+                    html! {
+                        <Card {card_id} hidden={*hidden} {playable} {tunable} {on_click} {on_drag_start} />
+                    }
                 })}
             </div>
         </div>
@@ -147,11 +270,22 @@ pub struct CharacterProps {
     pub char_statuses: Vec<StatusEntry>,
     pub team_statuses: Vec<StatusEntry>,
     pub hash: u64,
+    /// Skills that can currently be cast, i.e. that are affordable with the current dice and energy.
+    #[prop_or_default]
+    pub castable_skills: Vec<SkillId>,
+    #[prop_or_default]
+    pub on_cast_skill: Callback<SkillId>,
+    /// Switches to this character, if it is a legal move.
+    #[prop_or_default]
+    pub on_switch: Option<Callback<MouseEvent>>,
 }
 
 impl PartialEq for CharacterProps {
     fn eq(&self, other: &Self) -> bool {
-        self.is_active == other.is_active && self.hash == other.hash
+        self.is_active == other.is_active
+            && self.hash == other.hash
+            && self.castable_skills == other.castable_skills
+            && self.on_switch.is_some() == other.on_switch.is_some()
     }
 }
 
@@ -163,7 +297,9 @@ pub fn char_part(props: &CharacterProps) -> Html {
     let status_line = |class: &'static str, status: &'static Status, state: AppliedEffectState| {
         html! {
             <li class={class}>
-                {status.name}
+                <Tooltip content={html! { <StatusDetail {status} kind="Status" /> }}>
+                    {status.name}
+                </Tooltip>
                 {" "}
                 <StatusInfo {status} {state} compact={true} />
             </li>
@@ -173,8 +309,9 @@ pub fn char_part(props: &CharacterProps) -> Html {
         <div class={classes!(
             "char-card",
             if props.is_active { Some("is-active") } else { None },
-            if is_dead { Some("is-dead") } else { None })
-        } title="Character Card">
+            if is_dead { Some("is-dead") } else { None },
+            if props.on_switch.is_some() { Some("is-switchable") } else { None })
+        } title="Character Card" onclick={props.on_switch.clone()}>
             <h5>{char_card.name}</h5>
             <ul>
                 <li class="char-elements">
@@ -184,6 +321,32 @@ pub fn char_part(props: &CharacterProps) -> Html {
                 </li>
                 <li>{format!("HP: {}/{}", char_state.hp(), char_card.max_health)}</li>
                 <li>{format!("Energy: {}/{}", char_state.energy(), char_card.max_energy)}</li>
+                <li>
+                    <div class="char-skills">
+                        <h6>{"Skills:"}</h6>
+                        <ul>
+                            {for char_card.skills.iter().copied().map(|skill_id| {
+                                let skill = skill_id.skill();
+                                let is_castable = props.castable_skills.contains(&skill_id);
+                                let onclick = props.on_cast_skill.reform(move |_: MouseEvent| skill_id);
+                                html! {
+                                    <li class={classes!("char-skill", if is_castable { Some("is-castable") } else { None })}>
+                                        <button disabled={!is_castable} {onclick}>
+                                            <Tooltip content={html! { <SkillDetail {skill_id} /> }}>
+                                                {skill.name}
+                                            </Tooltip>
+                                        </button>
+                                        {" "}
+                                        <CostInfo cost={skill.cost} />
+                                        {for skill.deal_dmg.map(|dmg| html! {
+                                            <div class="char-skill-dmg">{describe_dmg(&dmg)}</div>
+                                        })}
+                                    </li>
+                                }
+                            })}
+                        </ul>
+                    </div>
+                </li>
                 {
                     if !is_dead {
                         html! {
@@ -196,7 +359,9 @@ pub fn char_part(props: &CharacterProps) -> Html {
                                             html! {
                                                 <li class={format!("status-equip equip-slot-{slot:?}")}>
                                                     {format!("{}: ", slot)}
-                                                    {status.name}
+                                                    <Tooltip content={html! { <StatusDetail {status} kind="Equipment" /> }}>
+                                                        {status.name}
+                                                    </Tooltip>
                                                     {" "}
                                                     <StatusInfo {status} {state} compact={true} />
                                                 </li>
@@ -246,7 +411,11 @@ fn summon_part(props: &SummonProps) -> Html {
     let status = summon_id.status();
     html! {
         <div class="summon">
-            <h5>{status.name}</h5>
+            <h5>
+                <Tooltip content={html! { <StatusDetail {status} kind="Summon" /> }}>
+                    {status.name}
+                </Tooltip>
+            </h5>
             <StatusInfo {status} state={summon.state} />
         </div>
     }
@@ -266,7 +435,11 @@ fn support_part(props: &SupportProps) -> Html {
     let status = support_id.status();
     html! {
         <div class="support">
-            <h5>{status.name}</h5>
+            <h5>
+                <Tooltip content={html! { <StatusDetail {status} kind="Support" /> }}>
+                    {status.name}
+                </Tooltip>
+            </h5>
             <StatusInfo {status} state={support.state} />
         </div>
     }
@@ -324,15 +497,48 @@ pub struct CardProps {
     pub card_id: CardId,
     #[prop_or(false)]
     pub hidden: bool,
+    /// Whether clicking the card plays it.
+    #[prop_or(false)]
+    pub playable: bool,
+    /// Whether the card can be dragged onto the dice for Elemental Tuning.
+    #[prop_or(false)]
+    pub tunable: bool,
+    #[prop_or_default]
+    pub on_click: Callback<MouseEvent>,
+    #[prop_or_default]
+    pub on_drag_start: Callback<DragEvent>,
 }
 
 #[function_component(Card)]
-fn card(CardProps { card_id, hidden }: &CardProps) -> Html {
+pub fn card(
+    CardProps {
+        card_id,
+        hidden,
+        playable,
+        tunable,
+        on_click,
+        on_drag_start,
+    }: &CardProps,
+) -> Html {
+    if *hidden {
+        return html! { <span class="card" title="Card" /> };
+    }
     let card = card_id.card();
     html! {
-        <span class="card" title="Card">
-            {if *hidden { "" } else { card.name }}
-        </span>
+        <Tooltip content={html! { <CardDetail card_id={*card_id} /> }}>
+            <span
+                class={classes!(
+                    "card",
+                    if *playable { Some("is-playable") } else { None },
+                    if *tunable { Some("is-tunable") } else { None })
+                }
+                onclick={if *playable { Some(on_click.clone()) } else { None }}
+                draggable={tunable.to_string()}
+                ondragstart={on_drag_start.clone()}
+            >
+                {card.name}
+            </span>
+        </Tooltip>
     }
 }
 
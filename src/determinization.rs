@@ -0,0 +1,156 @@
+use gitcg_sim::{deck::Deck, prelude::*, rand::prelude::*};
+use serde::{Deserialize, Serialize};
+
+use crate::{
+    app::{AppState, G},
+    state_edit::{deck, deck_mut},
+};
+
+/// Information needed by the search worker to sample plausible hands for a player whose hand
+/// is hidden from the search.
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct Determinization {
+    pub hidden_player: PlayerId,
+    /// Number of hands and deck orders to sample. Root action statistics are aggregated over all samples.
+    pub samples: u32,
+    /// Cards of the hidden player's decklist that have not been played or tuned yet.
+    pub remaining_cards: Vec<CardId>,
+}
+
+impl Determinization {
+    pub fn new(app: &AppState, hidden_player: PlayerId, samples: u32) -> Self {
+        let decklist = match hidden_player {
+            PlayerId::PlayerFirst => &app.decklists.0,
+            PlayerId::PlayerSecond => &app.decklists.1,
+        };
+        let mut remaining_cards: Vec<CardId> = decklist.cards.to_vec();
+        for entry in &app.move_log {
+            let Input::FromPlayer(player_id, action) = entry.action else {
+                continue;
+            };
+            if player_id != hidden_player {
+                continue;
+            }
+            let (PlayerAction::PlayCard(card_id, _) | PlayerAction::ElementalTuning(card_id)) =
+                action
+            else {
+                continue;
+            };
+            if let Some(i) = remaining_cards.iter().position(|&c| c == card_id) {
+                remaining_cards.swap_remove(i);
+            }
+        }
+        Self {
+            hidden_player,
+            samples,
+            remaining_cards,
+        }
+    }
+
+    /// Copies of the hidden game state, each with the hidden player's hand and deck replaced by a
+    /// shuffled order of the remaining cards: the hand is dealt first and the deck gets the rest.
+    pub fn sample_states(&self, game_state: &G, seed: u64) -> Vec<G> {
+        let mut rng = SmallRng::seed_from_u64(seed);
+        let hand_size = game_state
+            .game_state
+            .player(self.hidden_player)
+            .hand()
+            .len();
+        let deck_size = deck(game_state, self.hidden_player).count as usize;
+        (0..self.samples)
+            .map(|_| {
+                let (hand_cards, deck_cards) = self.deal(&mut rng, hand_size, deck_size);
+                let mut game_state = game_state.clone();
+                let hand = game_state
+                    .game_state
+                    .player_mut(self.hidden_player)
+                    .hand_mut();
+                hand.clear();
+                for card_id in hand_cards {
+                    hand.push(card_id);
+                }
+                *deck_mut(&mut game_state, self.hidden_player) = Deck::new(&deck_cards);
+                // Draws are taken from the nondeterminism handler's RNG, which would otherwise
+                // draw in the same order for every sample.
+                game_state.nd.state.rng = SmallRng::seed_from_u64(rng.gen());
+                game_state
+            })
+            .collect()
+    }
+
+    /// Shuffle the remaining cards and deal a hand and a deck of the given sizes from them.
+    fn deal(
+        &self,
+        rng: &mut SmallRng,
+        hand_size: usize,
+        deck_size: usize,
+    ) -> (Vec<CardId>, Vec<CardId>) {
+        let mut cards = self.remaining_cards.clone();
+        cards.shuffle(rng);
+        let deck_cards = cards
+            .iter()
+            .copied()
+            .skip(hand_size)
+            .take(deck_size)
+            .collect();
+        cards.truncate(hand_size);
+        (cards, deck_cards)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::app::{default_decklists, new_game};
+
+    const SEED: u64 = 1;
+
+    /// Determinization of the second player's hand at the start of a game.
+    fn opening_determinization(samples: u32) -> (G, Determinization) {
+        let decklists = default_decklists();
+        let game_state = new_game(&decklists, SEED);
+        let determinization = Determinization {
+            hidden_player: PlayerId::PlayerSecond,
+            samples,
+            remaining_cards: decklists.1.cards.to_vec(),
+        };
+        (game_state, determinization)
+    }
+
+    fn count(cards: &[CardId], card_id: CardId) -> usize {
+        cards.iter().filter(|&&c| c == card_id).count()
+    }
+
+    #[test]
+    fn sample_states_keep_hand_and_deck_sizes() {
+        let (game_state, determinization) = opening_determinization(4);
+        let player_id = determinization.hidden_player;
+        let hand_size = game_state.game_state.player(player_id).hand().len();
+        let deck_size = deck(&game_state, player_id).count;
+        let samples = determinization.sample_states(&game_state, SEED);
+        assert_eq!(4, samples.len());
+        for sample in &samples {
+            assert_eq!(hand_size, sample.game_state.player(player_id).hand().len());
+            assert_eq!(deck_size, deck(sample, player_id).count);
+        }
+    }
+
+    #[test]
+    fn deal_keeps_the_remaining_cards() {
+        let (game_state, determinization) = opening_determinization(1);
+        let player_id = determinization.hidden_player;
+        let hand_size = game_state.game_state.player(player_id).hand().len();
+        let deck_size = deck(&game_state, player_id).count as usize;
+        let remaining = &determinization.remaining_cards;
+        // At the start of the game, the hand and the deck hold the whole decklist.
+        assert_eq!(remaining.len(), hand_size + deck_size);
+        let mut rng = SmallRng::seed_from_u64(SEED);
+        let (hand, deck_cards) = determinization.deal(&mut rng, hand_size, deck_size);
+        assert_eq!(hand_size, hand.len());
+        assert_eq!(deck_size, deck_cards.len());
+        let dealt: Vec<CardId> = hand.into_iter().chain(deck_cards).collect();
+        for &card_id in remaining {
+            assert_eq!(count(remaining, card_id), count(&dealt, card_id));
+        }
+    }
+}
@@ -0,0 +1,133 @@
+use std::fmt::Display;
+
+use gitcg_sim::prelude::*;
+
+use crate::{
+    app::{describe_action, G},
+    search::SearchProgress,
+};
+
+/// Number of alternatives to the played move that are listed.
+const ALTERNATIVES: usize = 3;
+
+/// Maximum number of the AI's own follow-up moves taken from the principal variation.
+const FOLLOW_UP_MOVES: usize = 3;
+
+/// A short description of why the AI played a move, built from the search result.
+#[derive(Debug, Clone, PartialEq)]
+pub struct MoveExplanation {
+    pub player_id: PlayerId,
+    pub played: String,
+    /// Mean value of the played move from the AI's point of view, between 0 and 1.
+    pub value: Option<f32>,
+    /// Change of `value` since the previous move of the same player.
+    pub value_change: Option<f32>,
+    /// Other root actions with their share of the visits, most visited first.
+    pub alternatives: Vec<(String, f32)>,
+    /// The AI's next moves in the principal variation, with whether the opponent moves before.
+    pub follow_up: Vec<(String, bool)>,
+}
+
+impl MoveExplanation {
+    pub fn new(
+        game_state: &G,
+        player_id: PlayerId,
+        played: Input,
+        progress: &SearchProgress,
+        previous: Option<&MoveExplanation>,
+    ) -> Self {
+        let stats = &progress.action_stats;
+        let total_visits: u32 = stats.iter().map(|s| s.visits).sum();
+        let value = stats.iter().find(|s| s.action == played).map(|s| s.value);
+        let value_change = previous
+            .filter(|p| p.player_id == player_id)
+            .and_then(|p| Some(value? - p.value?));
+
+        let mut alternatives: Vec<_> = stats.iter().filter(|s| s.action != played).collect();
+        alternatives.sort_by_key(|s| std::cmp::Reverse(s.visits));
+        let alternatives = alternatives
+            .into_iter()
+            .take(ALTERNATIVES)
+            .map(|s| {
+                let share = if total_visits > 0 {
+                    s.visits as f32 / total_visits as f32
+                } else {
+                    0.0
+                };
+                (describe_action(game_state, s.action), share)
+            })
+            .collect();
+
+        let mut follow_up = vec![];
+        let pv: Vec<Input> = progress.result.pv.into_iter().copied().collect();
+        // Follow the principal variation only if it starts with the move that was played.
+        if pv.first() == Some(&played) {
+            let mut state = game_state.clone();
+            let mut opponent_moved = false;
+            for (i, &action) in pv.iter().enumerate() {
+                if i > 0 {
+                    match action.player() {
+                        Some(p) if p == player_id => {
+                            follow_up.push((describe_action(&state, action), opponent_moved));
+                        }
+                        Some(_) => opponent_moved = true,
+                        None => {}
+                    }
+                }
+                if follow_up.len() >= FOLLOW_UP_MOVES || state.advance(action).is_err() {
+                    break;
+                }
+            }
+        }
+
+        Self {
+            player_id,
+            played: describe_action(game_state, played),
+            value,
+            value_change,
+            alternatives,
+            follow_up,
+        }
+    }
+}
+
+impl Display for MoveExplanation {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        write!(f, "{} plays {}", self.player_id, self.played)?;
+        if let Some(value) = self.value {
+            write!(f, ", expecting a {:.1}% win rate", value * 100.0)?;
+            if let Some(change) = self.value_change {
+                write!(f, " ({:+.1} since its last move)", change * 100.0)?;
+            }
+        }
+        writeln!(f, ".")?;
+        if !self.alternatives.is_empty() {
+            let alternatives: Vec<String> = self
+                .alternatives
+                .iter()
+                .map(|(action, share)| format!("{action} ({:.0}%)", share * 100.0))
+                .collect();
+            writeln!(f, "Alternatives considered: {}", alternatives.join(", "))?;
+        }
+        if !self.follow_up.is_empty() {
+            let follow_up: Vec<String> = self
+                .follow_up
+                .iter()
+                .map(|(action, next_turn)| {
+                    if *next_turn {
+                        format!("{action} next turn")
+                    } else {
+                        action.clone()
+                    }
+                })
+                .collect();
+            writeln!(
+                f,
+                "Plan: {}, then {}",
+                self.played,
+                follow_up.join(", then ")
+            )?;
+        }
+        Ok(())
+    }
+}
@@ -1,17 +1,45 @@
 mod search;
 
+mod determinization;
+
+mod search_tree;
+
 mod components;
 
 mod actions_list;
 
+mod analysis;
+
 mod app;
 
 mod deck_editor;
 
+mod explanation;
+
+mod move_log;
+
+mod redraw;
+
+mod replay;
+
+mod reroll;
+
+mod review;
+
+mod settings;
+
+mod starting_character;
+
+mod state_edit;
+
+mod tooltip;
+
+mod win_graph;
+
 use crate::search::SearchWorker;
 use app::App;
 use wasm_bindgen::prelude::*;
-use yew_agent::PublicWorker;
+use yew_agent::PrivateWorker;
 
 pub fn main() {
     use js_sys::{global, Reflect};
@@ -0,0 +1,76 @@
+use yew::prelude::*;
+
+use crate::app::{describe_action_with_player, AppState};
+
+#[derive(Properties, PartialEq)]
+pub struct MoveLogProps {
+    pub app: UseReducerHandle<AppState>,
+    /// Index into the move log of the position shown on the board, or `None` for the current position.
+    pub selected: Option<usize>,
+    pub on_select: Callback<Option<usize>>,
+}
+
+#[function_component(MoveLog)]
+pub fn move_log(props: &MoveLogProps) -> Html {
+    let MoveLogProps {
+        app,
+        selected,
+        on_select,
+    } = props;
+    let n = app.move_log.len();
+    let selected = selected.filter(|&i| i < n);
+    let select = |i: Option<usize>| on_select.reform(move |_: MouseEvent| i);
+    let (prev, next) = match selected {
+        Some(i) => (
+            if i > 0 { Some(Some(i - 1)) } else { None },
+            Some(if i + 1 < n { Some(i + 1) } else { None }),
+        ),
+        None => (if n > 0 { Some(Some(n - 1)) } else { None }, None),
+    };
+    html! {
+        <div class="move-log">
+            <div class="move-log-nav">
+                <button
+                    onclick={select(Some(0))}
+                    disabled={n == 0 || selected == Some(0)}
+                    title="First move"
+                >{"\u{23ee}"}</button>
+                <button
+                    onclick={select(prev.flatten())}
+                    disabled={prev.is_none()}
+                    title="Step back"
+                >{"\u{25c0}"}</button>
+                <button
+                    onclick={select(next.flatten())}
+                    disabled={next.is_none()}
+                    title="Step forward"
+                >{"\u{25b6}"}</button>
+                <button
+                    onclick={select(None)}
+                    disabled={selected.is_none()}
+                    title="Current position"
+                >{"\u{23ed}"}</button>
+            </div>
+            <ol>
+                {for app.move_log.iter().enumerate().map(|(i, entry)| {
+                    let class = classes!(
+                        "move-log-entry",
+                        entry.action.player().map(|p| p.to_string()),
+                        if selected == Some(i) { Some("is-selected") } else { None },
+                    );
+                    html! {
+                        <li {class} onclick={select(Some(i))}>
+                            {describe_action_with_player(&entry.game_state, entry.action)}
+                        </li>
+                    }
+                })}
+                <li
+                    class={classes!("move-log-entry", if selected.is_none() { Some("is-selected") } else { None })}
+                    onclick={select(None)}
+                >
+                    <em>{"(Current position)"}</em>
+                </li>
+            </ol>
+        </div>
+    }
+}
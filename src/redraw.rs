@@ -0,0 +1,228 @@
+use gitcg_sim::{prelude::*, rand::prelude::*};
+use yew::prelude::*;
+
+use crate::{
+    app::{AppAction, AppState, G},
+    components::Card,
+    state_edit::{deck, StateEdit},
+};
+
+/// Human seats that have yet to choose the cards of their opening hand to redraw, in order.
+/// Each seat redraws once.
+#[derive(Debug, Clone, PartialEq)]
+pub struct PendingRedraw {
+    pub players: Vec<PlayerId>,
+}
+
+impl PendingRedraw {
+    pub fn new(players: Vec<PlayerId>) -> Option<Self> {
+        (!players.is_empty()).then_some(Self { players })
+    }
+
+    /// The seat currently choosing cards to redraw.
+    pub fn current(&self) -> Option<PlayerId> {
+        self.players.first().copied()
+    }
+
+    /// The current seat is done. Returns `None` once every seat is done.
+    pub fn next(mut self) -> Option<Self> {
+        if !self.players.is_empty() {
+            self.players.remove(0);
+        }
+        (!self.players.is_empty()).then_some(self)
+    }
+}
+
+fn hand(game_state: &G, player_id: PlayerId) -> Vec<CardId> {
+    game_state
+        .game_state
+        .player(player_id)
+        .hand()
+        .iter()
+        .copied()
+        .collect()
+}
+
+/// Swap the cards of the opening hand at the `selected` indices for cards drawn from the deck,
+/// which is the rest of the decklist. The swapped cards are then shuffled into the deck.
+///
+/// The redraw happens outside of the simulator, and the edit replaces the deck of the
+/// nondeterminism handler with one rebuilt from the decklist. This is only valid before the first
+/// draw after the opening hand, while the hand and the deck still hold the whole decklist.
+pub fn redraw(
+    game_state: &G,
+    decklist: &Decklist,
+    player_id: PlayerId,
+    selected: &[usize],
+    seed: u64,
+) -> StateEdit {
+    let mut rng = SmallRng::seed_from_u64(seed);
+    let hand = hand(game_state, player_id);
+    debug_assert_eq!(
+        decklist.cards.len(),
+        hand.len() + deck(game_state, player_id).count as usize,
+        "redraw after cards were drawn"
+    );
+    let mut deck: Vec<CardId> = decklist.cards.to_vec();
+    for card_id in &hand {
+        if let Some(i) = deck.iter().position(|c| c == card_id) {
+            deck.swap_remove(i);
+        }
+    }
+    deck.shuffle(&mut rng);
+    let mut swapped = vec![];
+    let hand = hand
+        .into_iter()
+        .enumerate()
+        .map(|(i, card_id)| {
+            if !selected.contains(&i) {
+                return card_id;
+            }
+            match deck.pop() {
+                Some(drawn) => {
+                    swapped.push(card_id);
+                    drawn
+                }
+                None => card_id,
+            }
+        })
+        .collect();
+    deck.extend(swapped);
+    deck.shuffle(&mut rng);
+    StateEdit::Redraw {
+        player_id,
+        hand,
+        deck,
+    }
+}
+
+/// Redraw rule for AI seats: keep one copy of each card and redraw the duplicates.
+pub fn ai_redraw_selection(game_state: &G, player_id: PlayerId) -> Vec<usize> {
+    let hand = hand(game_state, player_id);
+    hand.iter()
+        .enumerate()
+        .filter(|&(i, card_id)| hand[..i].contains(card_id))
+        .map(|(i, _)| i)
+        .collect()
+}
+
+#[derive(Properties, PartialEq)]
+pub struct RedrawSelectionProps {
+    pub app: UseReducerHandle<AppState>,
+}
+
+#[function_component(RedrawSelection)]
+pub fn redraw_selection(props: &RedrawSelectionProps) -> Html {
+    let app = &props.app;
+    let player_id = app.pending_redraw.as_ref().and_then(|p| p.current());
+    let selection = use_state(Vec::<usize>::new);
+    {
+        let selection = selection.clone();
+        use_effect_with_deps(move |_| selection.set(vec![]), player_id);
+    }
+    let Some(player_id) = player_id else {
+        return html! {};
+    };
+    let on_redraw = {
+        let app = app.clone();
+        let selection = selection.clone();
+        Callback::from(move |_: MouseEvent| app.dispatch(AppAction::Redraw((*selection).clone())))
+    };
+    let on_keep = {
+        let app = app.clone();
+        Callback::from(move |_: MouseEvent| app.dispatch(AppAction::KeepHand))
+    };
+    html! {
+        <div class="redraw">
+            <h2>{"Opening Hand"}</h2>
+            <h3>{format!("Player {player_id}: select cards to redraw")}</h3>
+            <div class="redraw-cards">
+                {for hand(&app.game_state, player_id).into_iter().enumerate().map(|(i, card_id)| {
+                    let on_click = {
+                        let selection = selection.clone();
+                        Callback::from(move |_: MouseEvent| {
+                            let mut cards = (*selection).clone();
+                            match cards.iter().position(|&c| c == i) {
+                                Some(pos) => {
+                                    cards.remove(pos);
+                                }
+                                None => cards.push(i),
+                            }
+                            selection.set(cards);
+                        })
+                    };
+                    html! {
+                        <span
+                            class={classes!("redraw-card", selection.contains(&i).then_some("is-selected"))}
+                            onclick={on_click}
+                        >
+                            <Card {card_id} />
+                        </span>
+                    }
+                })}
+            </div>
+            <button onclick={on_redraw} disabled={selection.is_empty()}>
+                {format!("Redraw {} cards", selection.len())}
+            </button>
+            <button onclick={on_keep}>{"Keep Hand"}</button>
+        </div>
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::app::{default_decklists, new_game};
+
+    const SEED: u64 = 1;
+
+    fn count(cards: &[CardId], card_id: CardId) -> usize {
+        cards.iter().filter(|&&c| c == card_id).count()
+    }
+
+    #[test]
+    fn redraw_keeps_the_hand_size_and_the_cards() {
+        let decklists = default_decklists();
+        let game_state = new_game(&decklists, SEED);
+        let player_id = PlayerId::PlayerFirst;
+        let before = hand(&game_state, player_id);
+        let StateEdit::Redraw { hand, deck, .. } =
+            redraw(&game_state, &decklists.0, player_id, &[0, 2], SEED)
+        else {
+            unreachable!()
+        };
+        assert_eq!(before.len(), hand.len());
+        for (i, (&kept, &card_id)) in before.iter().zip(&hand).enumerate() {
+            if i != 0 && i != 2 {
+                assert_eq!(kept, card_id);
+            }
+        }
+        let cards: Vec<CardId> = hand.into_iter().chain(deck).collect();
+        for &card_id in decklists.0.cards.iter() {
+            assert_eq!(count(&decklists.0.cards, card_id), count(&cards, card_id));
+        }
+    }
+
+    #[test]
+    fn ai_redraw_selection_picks_duplicates() {
+        let decklists = default_decklists();
+        let mut game_state = new_game(&decklists, SEED);
+        let player_id = PlayerId::PlayerFirst;
+        let cards = &decklists.0.cards;
+        let distinct: Vec<CardId> = cards
+            .iter()
+            .enumerate()
+            .filter(|&(i, card_id)| !cards[..i].contains(card_id))
+            .map(|(_, &card_id)| card_id)
+            .take(3)
+            .collect();
+        let (a, b, c) = (distinct[0], distinct[1], distinct[2]);
+        StateEdit::Redraw {
+            player_id,
+            hand: vec![a, b, a, c, b],
+            deck: vec![],
+        }
+        .apply(&mut game_state);
+        assert_eq!(vec![2, 4], ai_redraw_selection(&game_state, player_id));
+    }
+}
@@ -0,0 +1,479 @@
+use std::{cell::RefCell, fmt::Display, iter::Peekable, rc::Rc, slice};
+
+use gitcg_sim::prelude::*;
+use gloo::{
+    file::{callbacks::FileReader, Blob, ObjectUrl},
+    timers::callback::Timeout,
+};
+use serde::{Deserialize, Serialize};
+use wasm_bindgen::JsCast;
+use web_sys::{HtmlElement, HtmlInputElement};
+use yew::{html::onchange, prelude::*};
+
+use crate::{
+    app::{describe_action_with_player, new_game, AppAction, AppState, MoveLogEntry, G},
+    reroll::{is_roll_request, rolled_dice},
+    state_edit::StateEdit,
+};
+
+const REPLAY_FILE_NAME: &str = "gitcg_sim_replay.bin";
+
+/// How long the download URL is kept alive after the download was triggered.
+const DOWNLOAD_URL_TIMEOUT_MS: u32 = 10_000;
+
+/// A complete game: the decklists and random seed it was started with, and every input applied to it,
+/// including the `Input::NondetResult`s drawn by the nondeterminism handler.
+#[derive(Clone, Serialize, Deserialize)]
+pub struct Replay {
+    pub decklists: (Decklist, Decklist),
+    pub seed: u64,
+    pub inputs: Vec<Input>,
+    /// Edits to the game state made outside of the inputs, by the number of player inputs applied
+    /// before them.
+    pub edits: Vec<(usize, StateEdit)>,
+}
+
+#[derive(Debug, Clone)]
+pub enum ReplayError {
+    Format(String),
+    IllegalInput {
+        step: usize,
+        description: String,
+    },
+    AdvanceFailed {
+        step: usize,
+        description: String,
+        error: String,
+    },
+    /// The replay ends while nondeterministic results are still expected.
+    MissingNondetResult {
+        step: usize,
+    },
+}
+
+impl Display for ReplayError {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            ReplayError::Format(e) => write!(f, "Invalid replay data: {e}"),
+            ReplayError::IllegalInput { step, description } => {
+                write!(f, "Step {}: Illegal input: {description}", step + 1)
+            }
+            ReplayError::AdvanceFailed {
+                step,
+                description,
+                error,
+            } => write!(
+                f,
+                "Step {}: Failed to apply {description}: {error}",
+                step + 1
+            ),
+            ReplayError::MissingNondetResult { step } => {
+                write!(f, "Step {}: Missing nondeterministic result", step + 1)
+            }
+        }
+    }
+}
+
+impl Replay {
+    pub fn from_app_state(app: &AppState) -> Self {
+        Self {
+            decklists: app.decklists.as_ref().clone(),
+            seed: app.seed,
+            inputs: app
+                .move_log
+                .iter()
+                .flat_map(|entry| std::iter::once(entry.action).chain(entry.nondet.iter().copied()))
+                .collect(),
+            edits: app
+                .initial_edits
+                .iter()
+                .map(|edit| (0, edit.clone()))
+                .chain(app.move_log.iter().enumerate().flat_map(|(i, entry)| {
+                    entry.edits.iter().map(move |edit| (i + 1, edit.clone()))
+                }))
+                .collect(),
+        }
+    }
+
+    pub fn encode(&self) -> Result<Vec<u8>, ReplayError> {
+        bincode::serialize(self).map_err(|e| ReplayError::Format(e.to_string()))
+    }
+
+    pub fn decode(bytes: &[u8]) -> Result<Self, ReplayError> {
+        bincode::deserialize(bytes).map_err(|e| ReplayError::Format(e.to_string()))
+    }
+
+    /// Re-run the game from the start, validating each input against the legal actions at that point.
+    /// Nondeterministic results must match the ones drawn again from the seed, except for the dice of
+    /// the Roll Phase, which include the rerolls and only need the same number of dice.
+    /// Returns the final game state, the move log and the edits made before the first move, or the
+    /// first step that could not be applied.
+    pub fn reconstruct(&self) -> Result<(G, Vec<MoveLogEntry>, Vec<StateEdit>), ReplayError> {
+        let mut game_state = new_game(&self.decklists, self.seed);
+        let mut move_log = Vec::with_capacity(self.inputs.len());
+        let mut edits = self.edits.iter().peekable();
+        let initial_edits = apply_edits(&mut edits, 0, &mut game_state);
+        let mut inputs = self.inputs.iter().copied().enumerate();
+        let end = self.inputs.len();
+        while let Some((step, input)) = inputs.next() {
+            let description = describe_action_with_player(&game_state, input);
+            if !game_state.actions().iter().any(|&a| a == input) {
+                return Err(ReplayError::IllegalInput { step, description });
+            }
+            let prev = Rc::new(game_state.clone());
+            let advance_failed = |e: DispatchError| ReplayError::AdvanceFailed {
+                step,
+                description: description.clone(),
+                error: format!("{e:?}"),
+            };
+            let mut nondet = vec![];
+            let mut held = advance_until(&mut game_state, input, &mut nondet, is_roll_request)
+                .map_err(&advance_failed)?;
+            let mut matched = 0;
+            loop {
+                for &drawn in &nondet[matched..] {
+                    let (step, result) = next_nondet(&mut inputs, end)?;
+                    if Input::NondetResult(result) != drawn {
+                        let description =
+                            describe_action_with_player(&game_state, Input::NondetResult(result));
+                        return Err(ReplayError::IllegalInput { step, description });
+                    }
+                }
+                matched = nondet.len();
+                let Some((_, drawn)) = held else {
+                    break;
+                };
+                let (step, result) = next_nondet(&mut inputs, end)?;
+                if !is_reroll_of(&drawn, &result) {
+                    let description =
+                        describe_action_with_player(&game_state, Input::NondetResult(result));
+                    return Err(ReplayError::IllegalInput { step, description });
+                }
+                matched += 1;
+                held = answer_nondet(&mut game_state, result, &mut nondet, is_roll_request)
+                    .map_err(&advance_failed)?;
+            }
+            let edits = apply_edits(&mut edits, move_log.len() + 1, &mut game_state);
+            move_log.push(MoveLogEntry {
+                game_state: prev,
+                action: input,
+                nondet,
+                edits,
+            });
+        }
+        if let Some((moves, _)) = edits.next() {
+            return Err(ReplayError::Format(format!(
+                "State edit after move {moves} is out of order"
+            )));
+        }
+        Ok((game_state, move_log, initial_edits))
+    }
+}
+
+/// The next input of the replay, which has to be a nondeterministic result.
+fn next_nondet(
+    inputs: &mut impl Iterator<Item = (usize, Input)>,
+    end: usize,
+) -> Result<(usize, NondetResult), ReplayError> {
+    match inputs.next() {
+        Some((step, Input::NondetResult(result))) => Ok((step, result)),
+        Some((step, _)) => Err(ReplayError::MissingNondetResult { step }),
+        None => Err(ReplayError::MissingNondetResult { step: end }),
+    }
+}
+
+/// Whether `result` may be the dice of `drawn` after rerolls, which keep the number of dice of
+/// each seat.
+fn is_reroll_of(drawn: &NondetResult, result: &NondetResult) -> bool {
+    [PlayerId::PlayerFirst, PlayerId::PlayerSecond]
+        .into_iter()
+        .all(|player_id| {
+            match (
+                rolled_dice(drawn, player_id),
+                rolled_dice(result, player_id),
+            ) {
+                (Some(drawn), Some(dice)) => drawn.total() == dice.total(),
+                _ => false,
+            }
+        })
+}
+
+/// Apply the edits recorded after the given number of player inputs.
+fn apply_edits(
+    edits: &mut Peekable<slice::Iter<(usize, StateEdit)>>,
+    moves: usize,
+    game_state: &mut G,
+) -> Vec<StateEdit> {
+    let mut applied = vec![];
+    while let Some((_, edit)) = edits.next_if(|(i, _)| *i == moves) {
+        edit.apply(game_state);
+        applied.push(edit.clone());
+    }
+    applied
+}
+
+/// Apply a player input like `G::advance`, recording the `Input::NondetResult`s the
+/// nondeterminism handler drew while resolving it in `nondet`. Stops at the first request accepted
+/// by `hold`, which is returned along with the result the handler drew for it; the game state waits
+/// on it until `answer_nondet` feeds a result.
+///
+/// This is the loop of `GameStateWrapper::advance`, which samples nondeterministic results from
+/// `nd` and feeds them back without exposing them. It has to draw from `nd` in the same order, so
+/// that a game played through this function matches one played through `G::advance` from the same
+/// seed; `advance_until_matches_advance` checks this.
+pub fn advance_until(
+    game_state: &mut G,
+    input: Input,
+    nondet: &mut Vec<Input>,
+    hold: impl Fn(&NondetRequest) -> bool,
+) -> Result<Option<(NondetRequest, NondetResult)>, DispatchError> {
+    let res = game_state.game_state.advance(input)?;
+    resolve_nondet(game_state, res, nondet, hold)
+}
+
+/// Answer the request held by `advance_until` with `result`, then continue like `advance_until`.
+pub fn answer_nondet(
+    game_state: &mut G,
+    result: NondetResult,
+    nondet: &mut Vec<Input>,
+    hold: impl Fn(&NondetRequest) -> bool,
+) -> Result<Option<(NondetRequest, NondetResult)>, DispatchError> {
+    let input = Input::NondetResult(result);
+    nondet.push(input);
+    let res = game_state.game_state.advance(input)?;
+    resolve_nondet(game_state, res, nondet, hold)
+}
+
+fn resolve_nondet(
+    game_state: &mut G,
+    mut res: DispatchResult,
+    nondet: &mut Vec<Input>,
+    hold: impl Fn(&NondetRequest) -> bool,
+) -> Result<Option<(NondetRequest, NondetResult)>, DispatchError> {
+    while let DispatchResult::NondetRequest(req) = res {
+        let result = game_state.nd.sample_nondet(req);
+        if hold(&req) {
+            return Ok(Some((req, result)));
+        }
+        let input = Input::NondetResult(result);
+        nondet.push(input);
+        res = game_state.game_state.advance(input)?;
+    }
+    Ok(None)
+}
+
+fn download(file_name: &str, bytes: &[u8]) {
+    let url = ObjectUrl::from(Blob::new_with_options(
+        bytes,
+        Some("application/octet-stream"),
+    ));
+    let document = gloo::utils::document();
+    let Ok(a) = document.create_element("a") else {
+        return;
+    };
+    if a.set_attribute("href", &url).is_err() || a.set_attribute("download", file_name).is_err() {
+        return;
+    }
+    let body = gloo::utils::body();
+    if body.append_child(&a).is_err() {
+        return;
+    }
+    if let Ok(a) = a.dyn_into::<HtmlElement>() {
+        a.click();
+        a.remove();
+    }
+    // Some browsers start the download only after the click handler returned, so revoking the
+    // URL right away would cancel it.
+    Timeout::new(DOWNLOAD_URL_TIMEOUT_MS, move || drop(url)).forget();
+}
+
+#[derive(Properties, PartialEq)]
+pub struct ReplayControlsProps {
+    pub app: UseReducerHandle<AppState>,
+}
+
+#[function_component(ReplayControls)]
+pub fn replay_controls(props: &ReplayControlsProps) -> Html {
+    let app = &props.app;
+    let reader: Rc<RefCell<Option<FileReader>>> = use_mut_ref(|| None);
+    let on_export = {
+        let app = app.clone();
+        Callback::from(
+            move |_: MouseEvent| match Replay::from_app_state(&app).encode() {
+                Ok(bytes) => download(REPLAY_FILE_NAME, &bytes),
+                Err(e) => app.dispatch(AppAction::SetMessage(format!(
+                    "Failed to export replay: {e}"
+                ))),
+            },
+        )
+    };
+    let on_import = {
+        let app = app.clone();
+        Callback::from(move |e: onchange::Event| {
+            let Some(input) = e
+                .target()
+                .and_then(|t| t.dyn_into::<HtmlInputElement>().ok())
+            else {
+                return;
+            };
+            let Some(file) = input.files().and_then(|files| files.get(0)) else {
+                return;
+            };
+            input.set_value("");
+            let app = app.clone();
+            let task = gloo::file::callbacks::read_as_bytes(&file.into(), move |res| match res {
+                Ok(bytes) => match Replay::decode(&bytes) {
+                    Ok(replay) => app.dispatch(AppAction::LoadReplay(Rc::new(replay))),
+                    Err(e) => {
+                        app.dispatch(AppAction::SetMessage(format!("Failed to load replay: {e}")))
+                    }
+                },
+                Err(e) => app.dispatch(AppAction::SetMessage(format!(
+                    "Failed to read replay file: {e}"
+                ))),
+            });
+            *reader.borrow_mut() = Some(task);
+        })
+    };
+    html! {
+        <div class="replay-controls">
+            <h2>{"Replay"}</h2>
+            <div>
+                <button onclick={on_export}>{"Export Replay"}</button>
+            </div>
+            <div>
+                <label for="replay-import">
+                    {"Import Replay: "}
+                    <input id="replay-import" type="file" accept=".bin" onchange={on_import} />
+                </label>
+            </div>
+        </div>
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{app::default_decklists, redraw::redraw, reroll::PendingReroll};
+
+    const SEED: u64 = 1;
+
+    /// A replay of the first `moves` player inputs of a game where the first legal action is
+    /// always played.
+    fn first_moves_replay(moves: usize) -> Replay {
+        let decklists = default_decklists();
+        let mut game_state = new_game(&decklists, SEED);
+        let mut inputs = vec![];
+        for _ in 0..moves {
+            let input = game_state.actions()[0];
+            inputs.push(input);
+            advance_until(&mut game_state, input, &mut inputs, |_| false).unwrap();
+        }
+        Replay {
+            decklists,
+            seed: SEED,
+            inputs,
+            edits: vec![],
+        }
+    }
+
+    #[test]
+    fn advance_until_matches_advance() {
+        let decklists = default_decklists();
+        let mut recorded = new_game(&decklists, SEED);
+        let mut advanced = recorded.clone();
+        for _ in 0..20 {
+            let Some(&input) = advanced.actions().first() else {
+                break;
+            };
+            advance_until(&mut recorded, input, &mut vec![], |_| false).unwrap();
+            advanced.advance(input).unwrap();
+            assert_eq!(advanced.zobrist_hash(), recorded.zobrist_hash());
+        }
+    }
+
+    #[test]
+    fn reconstruct_legal_replay() {
+        let replay = first_moves_replay(6);
+        let (_, move_log, _) = replay.reconstruct().unwrap();
+        assert_eq!(6, move_log.len());
+    }
+
+    #[test]
+    fn reconstruct_applies_state_edits() {
+        let mut replay = first_moves_replay(2);
+        let player_id = PlayerId::PlayerFirst;
+        let game_state = new_game(&replay.decklists, SEED);
+        let edit = redraw(&game_state, &replay.decklists.0, player_id, &[0, 1], SEED);
+        replay.edits.push((0, edit.clone()));
+        let (_, move_log, initial_edits) = replay.reconstruct().unwrap();
+        assert_eq!(vec![edit.clone()], initial_edits);
+        let StateEdit::Redraw { hand, .. } = edit else {
+            unreachable!()
+        };
+        let first = &move_log[0].game_state.game_state;
+        let first_hand: Vec<CardId> = first.player(player_id).hand().iter().copied().collect();
+        assert_eq!(hand, first_hand);
+    }
+
+    #[test]
+    fn reconstruct_accepts_rerolled_dice() {
+        let decklists = default_decklists();
+        let mut game_state = new_game(&decklists, SEED);
+        let mut inputs = vec![];
+        let mut nondet = vec![];
+        let mut held = None;
+        for _ in 0..200 {
+            let input = game_state.actions()[0];
+            inputs.push(input);
+            held = advance_until(&mut game_state, input, &mut nondet, is_roll_request).unwrap();
+            inputs.append(&mut nondet);
+            if held.is_some() {
+                break;
+            }
+        }
+        let (request, result) = held.expect("no dice roll within 200 moves");
+        let mut pending = PendingReroll::new(request, result);
+        pending.reroll(&game_state, PlayerId::PlayerFirst, &[0, 1, 2], SEED);
+        answer_nondet(
+            &mut game_state,
+            pending.result(),
+            &mut nondet,
+            is_roll_request,
+        )
+        .unwrap();
+        inputs.append(&mut nondet);
+        let replay = Replay {
+            decklists,
+            seed: SEED,
+            inputs,
+            edits: vec![],
+        };
+        let (reconstructed, ..) = replay.reconstruct().unwrap();
+        assert_eq!(game_state.zobrist_hash(), reconstructed.zobrist_hash());
+    }
+
+    #[test]
+    fn reconstruct_reports_first_illegal_step() {
+        let mut replay = first_moves_replay(6);
+        let step = replay
+            .inputs
+            .iter()
+            .rposition(|input| matches!(input, Input::FromPlayer(..)))
+            .unwrap();
+        let Input::FromPlayer(player_id, _) = replay.inputs[step] else {
+            unreachable!()
+        };
+        // The opponent of the player to move can't act.
+        let other = match player_id {
+            PlayerId::PlayerFirst => PlayerId::PlayerSecond,
+            PlayerId::PlayerSecond => PlayerId::PlayerFirst,
+        };
+        replay.inputs[step] = Input::FromPlayer(other, PlayerAction::EndRound);
+        match replay.reconstruct() {
+            Err(ReplayError::IllegalInput { step: s, .. }) => assert_eq!(step, s),
+            Err(e) => panic!("unexpected error: {e}"),
+            Ok(..) => panic!("illegal input accepted"),
+        }
+    }
+}
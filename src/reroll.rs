@@ -0,0 +1,192 @@
+use gitcg_sim::{
+    prelude::{tcg_model::Dice, *},
+    rand::prelude::*,
+};
+use yew::prelude::*;
+
+use crate::app::G;
+
+/// The dice of the Roll Phase, held back from the simulator while the seats reroll. The game state
+/// waits on the roll request meanwhile, and continues once the final dice answer it.
+#[derive(Debug, Clone)]
+pub struct PendingReroll {
+    /// The simulator's request for the dice of both seats.
+    request: NondetRequest,
+    /// The dice the nondeterminism handler drew for the request, with the rerolls made so far.
+    result: NondetResult,
+    /// Number of rerolls made so far, so that each one draws different dice.
+    rerolls: u64,
+    /// Human seats that have yet to reroll, in order, with their remaining reroll chances.
+    pub players: Vec<(PlayerId, u8)>,
+}
+
+impl PendingReroll {
+    pub fn new(request: NondetRequest, result: NondetResult) -> Self {
+        Self {
+            request,
+            result,
+            rerolls: 0,
+            players: vec![],
+        }
+    }
+
+    /// The seat currently choosing dice to reroll, with its remaining chances.
+    pub fn current(&self) -> Option<(PlayerId, u8)> {
+        self.players.first().copied()
+    }
+
+    /// Whether every seat is done and the dice can be given to the simulator.
+    pub fn is_done(&self) -> bool {
+        self.players.is_empty()
+    }
+
+    /// The result that answers the roll request, with the rerolled dice.
+    pub fn result(&self) -> NondetResult {
+        self.result
+    }
+
+    /// Reroll chances the simulator grants the player for this roll.
+    pub fn chances(&self, player_id: PlayerId) -> u8 {
+        reroll_chances(&self.request, player_id)
+    }
+
+    /// The player's dice as rolled so far.
+    pub fn dice(&self, player_id: PlayerId) -> DiceCounter {
+        rolled_dice(&self.result, player_id).unwrap_or_default()
+    }
+
+    /// Roll the player's dice at the `selected` indices of `dice_vec` again. The new dice are drawn
+    /// by the nondeterminism handler of `game_state` for the same request, from an RNG seeded with
+    /// `seed` so that the draws of the game itself are unchanged.
+    pub fn reroll(&mut self, game_state: &G, player_id: PlayerId, selected: &[usize], seed: u64) {
+        if selected.is_empty() {
+            return;
+        }
+        let mut rng = SmallRng::seed_from_u64(seed.wrapping_add(self.rerolls));
+        self.rerolls += 1;
+        let mut nd = game_state.nd.clone();
+        nd.state.rng = SmallRng::seed_from_u64(rng.gen());
+        let Some(drawn) = rolled_dice(&nd.sample_nondet(self.request), player_id) else {
+            return;
+        };
+        let mut drawn = dice_vec(&drawn);
+        drawn.shuffle(&mut rng);
+        let mut drawn = drawn.into_iter();
+        let mut dice = DiceCounter::default();
+        for (i, d) in dice_vec(&self.dice(player_id)).into_iter().enumerate() {
+            let d = if selected.contains(&i) {
+                drawn.next().unwrap_or(d)
+            } else {
+                d
+            };
+            dice.add_single(d, 1);
+        }
+        set_rolled_dice(&mut self.result, player_id, dice);
+    }
+
+    /// Use up one reroll chance of the current seat.
+    pub fn use_chance(&mut self) {
+        if let Some((_, chances)) = self.players.first_mut() {
+            *chances = chances.saturating_sub(1);
+            if *chances == 0 {
+                self.players.remove(0);
+            }
+        }
+    }
+
+    /// The current seat keeps its dice.
+    pub fn keep(&mut self) {
+        if !self.players.is_empty() {
+            self.players.remove(0);
+        }
+    }
+}
+
+/// Whether the simulator asks for the dice of the Roll Phase.
+pub fn is_roll_request(request: &NondetRequest) -> bool {
+    matches!(request, NondetRequest::RollDice(..))
+}
+
+/// The dice rolled for the player, if `result` answers a dice roll request.
+pub fn rolled_dice(result: &NondetResult, player_id: PlayerId) -> Option<DiceCounter> {
+    match result {
+        NondetResult::ProvideDice(dice) => Some(*dice.get(player_id)),
+        _ => None,
+    }
+}
+
+fn set_rolled_dice(result: &mut NondetResult, player_id: PlayerId, dice: DiceCounter) {
+    if let NondetResult::ProvideDice(rolled) = result {
+        *rolled.get_mut(player_id) = dice;
+    }
+}
+
+/// The simulator builds the dice distribution of each seat from its statuses, including the
+/// number of rerolls they grant on top of the one of every round.
+fn reroll_chances(request: &NondetRequest, player_id: PlayerId) -> u8 {
+    match request {
+        NondetRequest::RollDice(dists) => dists.get(player_id).reroll_times,
+        _ => 0,
+    }
+}
+
+/// The dice of the counter one by one, in the order `DiceList` shows them.
+pub fn dice_vec(dice: &DiceCounter) -> Vec<Dice> {
+    dice.tally()
+        .iter()
+        .copied()
+        .flat_map(|(d, c)| std::iter::repeat(d).take(c as usize))
+        .collect()
+}
+
+/// Reroll rule for AI seats: keep Omni dice and dice of the element of a living character,
+/// reroll the others.
+pub fn ai_reroll_selection(game_state: &G, player_id: PlayerId, dice: &DiceCounter) -> Vec<usize> {
+    let player = game_state.game_state.player(player_id);
+    let elements: Vec<Element> = player
+        .char_states()
+        .iter_all()
+        .filter(|c| c.hp() > 0)
+        .map(|c| c.char_id().char_card().elem)
+        .collect();
+    dice_vec(dice)
+        .into_iter()
+        .enumerate()
+        .filter(|(_, d)| match d {
+            Dice::Omni => false,
+            Dice::Elem(e) => !elements.contains(e),
+        })
+        .map(|(i, _)| i)
+        .collect()
+}
+
+#[derive(Properties, PartialEq)]
+pub struct RerollControlsProps {
+    pub player_id: PlayerId,
+    pub chances: u8,
+    /// Number of dice selected in the dice list.
+    pub selected: usize,
+    pub on_reroll: Callback<()>,
+    pub on_keep: Callback<()>,
+}
+
+#[function_component(RerollControls)]
+pub fn reroll_controls(props: &RerollControlsProps) -> Html {
+    html! {
+        <div class="reroll">
+            <div>
+                {format!(
+                    "Player {}: select dice to reroll ({} reroll{} left)",
+                    props.player_id,
+                    props.chances,
+                    if props.chances == 1 { "" } else { "s" }
+                )}
+            </div>
+            <button
+                onclick={props.on_reroll.reform(|_| ())}
+                disabled={props.selected == 0}
+            >{format!("Reroll {} dice", props.selected)}</button>
+            <button onclick={props.on_keep.reform(|_| ())}>{"Keep Dice"}</button>
+        </div>
+    }
+}
@@ -0,0 +1,215 @@
+use gitcg_sim::prelude::*;
+use yew::prelude::*;
+
+use crate::{
+    app::{describe_action, AppAction, AppState},
+    search::{most_visited, ActionStats, SearchProgress},
+};
+
+/// Minimum loss of win rate for a move to be flagged.
+const BLUNDER_THRESHOLD: f32 = 0.1;
+
+/// A human move whose value was significantly worse than the best move found by the review.
+#[derive(Debug, Clone, PartialEq)]
+pub struct Blunder {
+    /// Index of the move in the move log.
+    pub index: usize,
+    pub player_id: PlayerId,
+    pub played: String,
+    pub best: String,
+    /// Value of the played move minus the value of the best move.
+    pub delta: f32,
+}
+
+/// Progress of a post-game review, which searches every human move of the game in turn.
+#[derive(Debug, Clone, PartialEq, Default)]
+pub struct Review {
+    /// Move log indices of the human moves to review.
+    pub positions: Vec<usize>,
+    /// Number of positions reviewed so far.
+    pub reviewed: usize,
+    /// Number of reviewed positions whose search failed or was stopped.
+    pub skipped: usize,
+    pub blunders: Vec<Blunder>,
+}
+
+impl Review {
+    pub fn new(app: &AppState) -> Self {
+        let positions = app
+            .move_log
+            .iter()
+            .enumerate()
+            .filter(|(_, entry)| {
+                matches!(entry.action, Input::FromPlayer(p, _) if !app.controllers.is_ai(p))
+            })
+            .map(|(i, _)| i)
+            .collect();
+        Self {
+            positions,
+            ..Default::default()
+        }
+    }
+
+    /// Move log index of the next position to search.
+    pub fn next_position(&self) -> Option<usize> {
+        self.positions.get(self.reviewed).copied()
+    }
+
+    pub fn is_finished(&self) -> bool {
+        self.reviewed >= self.positions.len()
+    }
+
+    /// Move on from the move at `index` of the move log without a search result.
+    pub fn skip(&mut self, index: usize) {
+        if self.next_position() == Some(index) {
+            self.reviewed += 1;
+            self.skipped += 1;
+        }
+    }
+
+    /// Record the search result for the move at `index` of the move log.
+    pub fn add_result(&mut self, app: &AppState, index: usize, progress: &SearchProgress) {
+        if self.next_position() != Some(index) {
+            return;
+        }
+        self.reviewed += 1;
+        let Some(entry) = app.move_log.get(index) else {
+            return;
+        };
+        let Input::FromPlayer(player_id, _) = entry.action else {
+            return;
+        };
+        let Some((best, delta)) = blunder(&progress.action_stats, entry.action) else {
+            return;
+        };
+        self.blunders.push(Blunder {
+            index,
+            player_id,
+            played: describe_action(&entry.game_state, entry.action),
+            best: describe_action(&entry.game_state, best),
+            delta,
+        });
+    }
+}
+
+/// The best move and the value lost by playing `played` instead, if the loss reaches
+/// `BLUNDER_THRESHOLD`. The best move is the most visited root action of the search.
+fn blunder(stats: &[ActionStats], played: Input) -> Option<(Input, f32)> {
+    let value_of = |action: Input| {
+        stats
+            .iter()
+            .find(|s| s.action == action && s.visits > 0)
+            .map(|s| s.value)
+    };
+    let best = most_visited(stats)?;
+    // Moves the search never tried can't be evaluated.
+    let delta = value_of(played)? - value_of(best)?;
+    (best != played && delta <= -BLUNDER_THRESHOLD).then_some((best, delta))
+}
+
+#[derive(Properties, PartialEq)]
+pub struct ReviewPanelProps {
+    pub app: UseReducerHandle<AppState>,
+    pub on_select: Callback<Option<usize>>,
+}
+
+#[function_component(ReviewPanel)]
+pub fn review_panel(props: &ReviewPanelProps) -> Html {
+    let app = &props.app;
+    let on_start = {
+        let app = app.clone();
+        Callback::from(move |_: MouseEvent| app.dispatch(AppAction::StartReview))
+    };
+    let Some(review) = &app.review else {
+        return html! {
+            <div class="review">
+                <button onclick={on_start} disabled={app.pending_start.is_some()}>
+                    {"Review Game"}
+                </button>
+            </div>
+        };
+    };
+    html! {
+        <div class="review">
+            <div>
+                {if review.is_finished() {
+                    format!("Reviewed {} moves, {} blunders found", review.positions.len(), review.blunders.len())
+                } else {
+                    format!("Reviewing move {}/{}...", review.reviewed + 1, review.positions.len())
+                }}
+                {if review.skipped > 0 {
+                    format!(" ({} moves could not be searched)", review.skipped)
+                } else {
+                    String::new()
+                }}
+            </div>
+            <ul class="review-blunders">
+                {for review.blunders.iter().map(|blunder| {
+                    let onclick = props.on_select.reform({
+                        let index = blunder.index;
+                        move |_: MouseEvent| Some(index)
+                    });
+                    html! {
+                        <li class={blunder.player_id.to_string()} {onclick}>
+                            {format!(
+                                "Move {} ({}): played {}, better was {} ({:+.1}%)",
+                                blunder.index + 1,
+                                blunder.player_id,
+                                blunder.played,
+                                blunder.best,
+                                blunder.delta * 100.0
+                            )}
+                        </li>
+                    }
+                })}
+            </ul>
+        </div>
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const BEST: Input = Input::FromPlayer(PlayerId::PlayerFirst, PlayerAction::EndRound);
+    const PLAYED: Input =
+        Input::FromPlayer(PlayerId::PlayerFirst, PlayerAction::SwitchCharacter(1));
+
+    fn stats(played_value: f32) -> Vec<ActionStats> {
+        vec![
+            ActionStats {
+                action: BEST,
+                visits: 100,
+                value: 0.6,
+            },
+            ActionStats {
+                action: PLAYED,
+                visits: 20,
+                value: played_value,
+            },
+        ]
+    }
+
+    #[test]
+    fn blunder_flags_only_drops_above_threshold() {
+        assert_eq!(None, blunder(&stats(0.55), PLAYED));
+        let (best, delta) = blunder(&stats(0.45), PLAYED).unwrap();
+        assert_eq!(BEST, best);
+        assert!((delta + 0.15).abs() < 1e-6);
+        // Playing the best move is never a blunder.
+        assert_eq!(None, blunder(&stats(0.0), BEST));
+    }
+
+    #[test]
+    fn skip_moves_past_the_reviewed_position_only() {
+        let mut review = Review {
+            positions: vec![2, 5, 7],
+            ..Default::default()
+        };
+        review.skip(5);
+        assert_eq!(Some(2), review.next_position());
+        review.skip(2);
+        assert_eq!(Some(5), review.next_position());
+        assert_eq!((1, 1), (review.reviewed, review.skipped));
+    }
+}
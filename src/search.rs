@@ -1,6 +1,6 @@
-use gloo::utils::format::JsValueSerdeExt;
+use gloo::{timers::callback::Timeout, utils::format::JsValueSerdeExt};
 use instant::Instant;
-use std::rc::Rc;
+use std::{collections::VecDeque, rc::Rc};
 use wasm_bindgen::JsValue;
 use yew_agent::*;
 
@@ -15,7 +15,11 @@ use gitcg_sim_search::{
 
 use serde::{Deserialize, Serialize};
 
-use crate::app::{describe_action_with_player, G};
+use crate::{
+    app::{describe_action_with_player, opponent, G},
+    determinization::Determinization,
+    settings::SearchSettings,
+};
 
 #[derive(Serialize, Deserialize)]
 pub struct WorkerMessage {
@@ -30,34 +34,199 @@ pub struct SearchWorker {
     pub search: MCTS<G, DefaultEvalPolicy, RuleBasedPuct>,
     pub search_steps: Option<SearchSteps>,
     pub solution: Option<SearchResult<G>>,
+    /// Pending timer for the next step of the active search.
+    timer: Option<Timeout>,
+    /// Player the search tree was built for. The tree is only reused for the same player.
+    tree_player: Option<PlayerId>,
+    /// Configuration of `search`. The search tree is kept when the same configuration is sent again.
+    config: MCTSConfig,
 }
 
+/// Identifies a search started by the main thread, so that responses for an earlier search can be discarded.
+pub type SearchId = u32;
+
 #[derive(Debug, Clone, Serialize, Deserialize)]
 pub enum SearchAction {
     Start {
+        search_id: SearchId,
         maximize_player: PlayerId,
         game_state: Rc<G>,
-        steps: u32,
+        stop: StopCondition,
+        move_selection: MoveSelection,
+        determinization: Option<Determinization>,
     },
-    Step,
     Abandon,
     SetConfig(MCTSConfig),
 }
 
-#[derive(Clone, Serialize, Deserialize, Default)]
-pub struct SearchReturn(pub bool, pub Option<SearchResult<G>>, pub u128);
+/// Messages the worker schedules for itself.
+pub enum SearchMessage {
+    /// Run the next step of the given search, if it is still active.
+    Step(SearchId),
+}
+
+/// When the worker ends a search. The search stops as soon as any of the limits is reached.
+#[derive(Debug, Clone, Copy, PartialEq, Serialize, Deserialize)]
+pub struct StopCondition {
+    /// Maximum number of steps, each limited by the configured time per step.
+    pub steps: u32,
+    /// Total search time across all steps.
+    pub time_budget_ms: Option<u32>,
+    /// Number of consecutive steps the best root action must stay unchanged.
+    pub converge_steps: Option<u32>,
+}
+
+/// Stop condition while pondering. The search is abandoned as soon as the human moves,
+/// so this only bounds the work done while the human is away.
+pub const PONDER_STOP_CONDITION: StopCondition = StopCondition {
+    steps: 60,
+    time_budget_ms: None,
+    converge_steps: None,
+};
+
+/// Stop condition for the short search behind a hint.
+pub const HINT_STOP_CONDITION: StopCondition = StopCondition {
+    steps: 3,
+    time_budget_ms: Some(1500),
+    converge_steps: None,
+};
+
+/// Stop condition for the search of each position during a post-game review.
+pub const REVIEW_STOP_CONDITION: StopCondition = StopCondition {
+    steps: 2,
+    time_budget_ms: Some(1000),
+    converge_steps: None,
+};
+
+/// Stop condition for evaluating each starting character of an AI seat.
+pub const STARTING_CHARACTER_STOP_CONDITION: StopCondition = StopCondition {
+    steps: 1,
+    time_budget_ms: Some(500),
+    converge_steps: None,
+};
+
+/// How the move to play is chosen once the search finishes.
+#[derive(Debug, Clone, Copy, PartialEq, Serialize, Deserialize)]
+pub enum MoveSelection {
+    /// Play the head of the principal variation.
+    Best,
+    /// Sample a root action with probability proportional to `visits ^ (1 / temperature)`.
+    Sample { temperature: f32 },
+}
+
+/// Visit statistics of an action from the root of the search tree.
+#[derive(Debug, Clone, Copy, Serialize, Deserialize)]
+pub struct ActionStats {
+    pub action: Input,
+    pub visits: u32,
+    pub value: f32,
+}
+
+/// A node of the search tree as sent to the main thread for display.
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct SearchTreeNode {
+    /// Description of the action leading to this node, or `None` for the root.
+    pub action: Option<String>,
+    pub visits: u32,
+    pub value: f32,
+    pub prior: Option<f32>,
+    pub children: Vec<Rc<SearchTreeNode>>,
+}
+
+/// State of a search after a number of steps.
+#[derive(Clone, Serialize, Deserialize)]
+pub struct SearchProgress {
+    /// Zobrist hash of the searched position.
+    pub hash: u64,
+    pub step: u32,
+    pub total_steps: u32,
+    pub total_time_ns: u128,
+    /// Evaluation, principal variation and counters accumulated over all steps so far.
+    pub result: SearchResult<G>,
+    pub action_stats: Vec<ActionStats>,
+    /// Summary of each worker's counters when the search runs on several workers.
+    #[serde(default)]
+    pub worker_summaries: Vec<String>,
+}
+
+#[derive(Clone, Serialize, Deserialize)]
+pub enum SearchReturn {
+    Started {
+        search_id: SearchId,
+    },
+    Progress {
+        search_id: SearchId,
+        progress: SearchProgress,
+    },
+    Finished {
+        search_id: SearchId,
+        /// The move chosen according to the search's `MoveSelection`.
+        best: Input,
+        progress: SearchProgress,
+        search_tree: Option<Rc<SearchTreeNode>>,
+    },
+    Abandoned {
+        search_id: SearchId,
+    },
+    Error {
+        search_id: SearchId,
+        message: String,
+    },
+}
+
+impl SearchReturn {
+    pub fn search_id(&self) -> SearchId {
+        match self {
+            SearchReturn::Started { search_id }
+            | SearchReturn::Progress { search_id, .. }
+            | SearchReturn::Finished { search_id, .. }
+            | SearchReturn::Abandoned { search_id }
+            | SearchReturn::Error { search_id, .. } => *search_id,
+        }
+    }
+}
 
 #[derive(Clone)]
 pub struct SearchSteps {
-    pub total_time_ms: u128,
-    pub steps_remaining: u32,
+    pub search_id: SearchId,
+    pub total_time_ns: u128,
+    pub step: u32,
+    pub stop: StopCondition,
     pub maximize_player: PlayerId,
+    pub move_selection: MoveSelection,
     pub game_state: G,
+    /// The main thread bridge that started the search.
+    pub handler_id: HandlerId,
+    /// Best root action after the latest step, and for how many steps it has not changed.
+    pub best_action: Option<Input>,
+    pub stable_steps: u32,
+    /// Sampled states for the hidden player's hand, searched in turn. Empty if the search is
+    /// not determinized.
+    pub determinized_states: Vec<G>,
+    /// Root action statistics from the latest step of each determinized state.
+    pub determinized_stats: Vec<Vec<ActionStats>>,
+}
+
+impl SearchSteps {
+    fn should_stop(&self) -> bool {
+        let stop = &self.stop;
+        self.step >= stop.steps
+            || stop
+                .time_budget_ms
+                .is_some_and(|ms| self.total_time_ns >= ms as u128 * 1_000_000)
+            || stop.converge_steps.is_some_and(|n| self.stable_steps >= n)
+    }
 }
 
 const TIME_LIMIT_MS: u128 = 500;
 
-const DEFAULT_CONFIG: MCTSConfig = {
+/// Depth of the search tree sent to the main thread.
+const SEARCH_TREE_DEPTH: u8 = 4;
+
+/// How far below the previous root to look for the position of a new search.
+const REROOT_MAX_DEPTH: u8 = 8;
+
+pub const DEFAULT_CONFIG: MCTSConfig = {
     let tt_size_mb = 32;
     let parallel = false;
     let random_playout_iters = 10;
@@ -81,9 +250,9 @@ const DEFAULT_CONFIG: MCTSConfig = {
 };
 
 impl Worker for SearchWorker {
-    type Reach = Public<Self>;
+    type Reach = Private<Self>;
 
-    type Message = ();
+    type Message = SearchMessage;
 
     type Input = SearchAction;
 
@@ -103,38 +272,96 @@ impl Worker for SearchWorker {
             ),
             search_steps: None,
             solution: None,
+            timer: None,
+            tree_player: None,
+            config: DEFAULT_CONFIG,
         }
     }
 
     fn handle_input(&mut self, msg: Self::Input, id: HandlerId) {
         match msg {
             SearchAction::Start {
+                search_id,
                 maximize_player,
                 game_state,
-                steps,
+                stop,
+                move_selection,
+                determinization,
             } => {
+                let determinization = determinization.filter(|d| d.samples > 1);
+                let hidden_player = determinization.as_ref().map(|d| d.hidden_player);
+                let reused = if self.tree_player == Some(maximize_player) {
+                    self.reroot(&game_state, hidden_player)
+                } else {
+                    None
+                };
+                self.tree_player = Some(maximize_player);
+                let mut determinized_states = determinization
+                    .map(|d| {
+                        let seed = (js_sys::Math::random() * u64::MAX as f64) as u64;
+                        d.sample_states(&game_state, seed)
+                    })
+                    .unwrap_or_default();
+                // The reused subtree was explored under an earlier sample of the hidden hand, so
+                // search that sample first.
+                if let (Some(state), Some(first)) = (reused, determinized_states.first_mut()) {
+                    *first = state;
+                }
+                let determinized_stats = vec![vec![]; determinized_states.len()];
                 self.search_steps = Some(SearchSteps {
-                    steps_remaining: steps,
+                    search_id,
+                    step: 0,
+                    stop,
                     game_state: game_state.as_ref().clone(),
                     maximize_player,
-                    total_time_ms: 0,
+                    move_selection,
+                    total_time_ns: 0,
+                    handler_id: id,
+                    best_action: None,
+                    stable_steps: 0,
+                    determinized_states,
+                    determinized_stats,
                 });
                 self.solution = None;
-                self.link.respond(id, SearchReturn::default());
+                self.link.respond(id, SearchReturn::Started { search_id });
+                self.schedule_step(search_id);
             }
             SearchAction::Abandon => {
-                self.search_steps = None;
+                self.timer = None;
                 self.solution = None;
-                self.link.respond(id, SearchReturn::default());
+                if let Some(search_steps) = self.search_steps.take() {
+                    let search_id = search_steps.search_id;
+                    self.link.respond(id, SearchReturn::Abandoned { search_id });
+                }
             }
-            SearchAction::Step => self.step(id),
             SearchAction::SetConfig(c) => {
-                self.search.config = c;
+                if self.config == c {
+                    return;
+                }
+                gloo::console::log!(
+                    "Search config updated: ",
+                    JsValue::from_serde::<MCTSConfig>(&c).unwrap()
+                );
+                self.search = MCTS::new_with_eval_policy_and_selection_policy(
+                    c,
+                    Default::default(),
+                    RuleBasedPuct,
+                );
+                self.config = c;
+                self.tree_player = None;
             }
         }
     }
 
-    fn update(&mut self, _msg: Self::Message) {}
+    fn update(&mut self, msg: Self::Message) {
+        match msg {
+            SearchMessage::Step(search_id) => {
+                if matches!(&self.search_steps, Some(s) if s.search_id == search_id) {
+                    self.step();
+                }
+            }
+        }
+    }
 
     fn name_of_resource() -> &'static str {
         "worker.js"
@@ -146,49 +373,65 @@ impl Worker for SearchWorker {
 }
 
 impl SearchWorker {
-    fn step(&mut self, id: HandlerId) {
-        let Some(mut search_steps) = self.search_steps.clone() else {
-            return;
+    /// Make the node for `game_state` the search root if the previous search already explored it,
+    /// keeping the statistics of its subtree. Returns the state of that node if found.
+    /// For a search over sampled hands of `hidden_player`, nodes are matched on the public
+    /// information only, since their states hold an earlier sample of the hidden hand.
+    fn reroot(&mut self, game_state: &G, hidden_player: Option<PlayerId>) -> Option<G> {
+        let (_, root) = self.search.root?;
+        let hash = game_state.zobrist_hash();
+        let public_hash = |state: &G| match hidden_player {
+            Some(player_id) => {
+                let mut state = state.clone();
+                state.hide_private_information(player_id);
+                state.zobrist_hash()
+            }
+            None => state.zobrist_hash(),
         };
-        if search_steps.steps_remaining == 0 {
-            if let Some((_, root)) = self.search.root {
-                gloo::console::log!("root");
-                if let Some((root, initial_state)) = self
-                    .search
-                    .tree
-                    .get(root)
-                    .map(|root_node| (root_node.token(), root_node.data.state.clone()))
-                {
-                    gloo::console::log!(format!(
-                        "Search Finish: Principal Variation = {:?}",
-                        self.solution.as_ref().map(|s| s
-                            .pv
-                            .into_iter()
-                            .map(|&action| describe_action_with_player(&initial_state, action))
-                            .collect::<Vec<_>>())
-                    ));
-                    gloo::console::log!(
-                        "MCTS Tree: ",
-                        JsValue::from_serde(&self.search.dump_tree(root, 4, &|action| {
-                            describe_action_with_player(&initial_state, action)
-                        }))
-                        .unwrap_or_default()
-                    );
-                    gloo::console::log!("-------------------");
+        let mut queue = VecDeque::from([(root, 0u8)]);
+        while let Some((token, depth)) = queue.pop_front() {
+            let Some(node) = self.search.tree.get(token) else {
+                continue;
+            };
+            if public_hash(&node.data.state) == hash {
+                let state = node.data.state.clone();
+                self.search.root = Some((state.zobrist_hash(), token));
+                return Some(state);
+            }
+            if depth < REROOT_MAX_DEPTH {
+                for child in token.children(&self.search.tree) {
+                    queue.push_back((child.token(), depth + 1));
                 }
             }
-            self.link.respond(
-                id,
-                SearchReturn(true, self.solution.clone(), search_steps.total_time_ms),
-            );
-            return;
         }
+        None
+    }
+
+    /// Run the next step of the active search from a timer, so that messages from the main thread
+    /// (such as `Abandon`) are handled between steps.
+    fn schedule_step(&mut self, search_id: SearchId) {
+        let step = self.link.callback(move |()| SearchMessage::Step(search_id));
+        self.timer = Some(Timeout::new(0, move || step(())));
+    }
+
+    fn step(&mut self) {
+        self.timer = None;
+        let Some(mut search_steps) = self.search_steps.clone() else {
+            return;
+        };
+        let search_id = search_steps.search_id;
 
         let t0 = Instant::now();
         gloo::console::log!(format!("Start time = {t0:?}"));
-        let mut res = self
-            .search
-            .search(&search_steps.game_state, search_steps.maximize_player);
+        let sample = match search_steps.determinized_states.len() {
+            0 => None,
+            n => Some(search_steps.step as usize % n),
+        };
+        let game_state = match sample {
+            Some(i) => &search_steps.determinized_states[i],
+            None => &search_steps.game_state,
+        };
+        let mut res = self.search.search(game_state, search_steps.maximize_player);
         gloo::console::log!(format!("Step {:?}", res.pv.head()));
         gloo::console::log!(format!(
             "Root: {}",
@@ -203,9 +446,8 @@ impl SearchWorker {
 
         let res1 = self.solution.clone().unwrap_or_default();
 
-        search_steps.total_time_ms += dt;
-        search_steps.steps_remaining -= 1;
-        let t = search_steps.total_time_ms;
+        search_steps.total_time_ns += dt;
+        search_steps.step += 1;
         {
             res.counter.add_in_place(&res1.counter);
             if res1.pv.len() >= res.pv.len() {
@@ -213,9 +455,640 @@ impl SearchWorker {
                 res.eval = res1.eval;
             }
         }
+        if let Some(i) = sample {
+            search_steps.determinized_stats[i] = self.root_action_stats();
+        }
+        self.solution = Some(res.clone());
+        let best_action = self.best_action(&search_steps);
+        if best_action.is_some() && best_action == search_steps.best_action {
+            search_steps.stable_steps += 1;
+        } else {
+            search_steps.best_action = best_action;
+            search_steps.stable_steps = 0;
+        }
+        if search_steps.should_stop() {
+            self.finish(&search_steps);
+            return;
+        }
+        let progress = self.progress(&search_steps, res);
+        let id = search_steps.handler_id;
         self.search_steps = Some(search_steps);
-        self.solution = Some(res);
-        self.link
-            .respond(id, SearchReturn(false, self.solution.clone(), t));
+        self.link.respond(
+            id,
+            SearchReturn::Progress {
+                search_id,
+                progress,
+            },
+        );
+        self.schedule_step(search_id);
+    }
+
+    fn finish(&mut self, search_steps: &SearchSteps) {
+        let search_id = search_steps.search_id;
+        let id = search_steps.handler_id;
+        self.search_steps = None;
+        let Some(solution) = self.solution.clone() else {
+            self.link.respond(
+                id,
+                SearchReturn::Error {
+                    search_id,
+                    message: "Search finished without a result".to_string(),
+                },
+            );
+            return;
+        };
+        if let Some((_, root)) = self.search.root {
+            if let Some((root, initial_state)) = self
+                .search
+                .tree
+                .get(root)
+                .map(|root_node| (root_node.token(), root_node.data.state.clone()))
+            {
+                gloo::console::log!(format!(
+                    "Search Finish: Principal Variation = {:?}",
+                    solution
+                        .pv
+                        .into_iter()
+                        .map(|&action| describe_action_with_player(&initial_state, action))
+                        .collect::<Vec<_>>()
+                ));
+                gloo::console::log!(
+                    "MCTS Tree: ",
+                    JsValue::from_serde(&self.search.dump_tree(root, 4, &|action| {
+                        describe_action_with_player(&initial_state, action)
+                    }))
+                    .unwrap_or_default()
+                );
+                gloo::console::log!("-------------------");
+            }
+        }
+        let Some(best) = self.select_move(search_steps) else {
+            self.link.respond(
+                id,
+                SearchReturn::Error {
+                    search_id,
+                    message: "No move found".to_string(),
+                },
+            );
+            return;
+        };
+        let search_tree = self.search_tree(SEARCH_TREE_DEPTH).map(Rc::new);
+        let progress = self.progress(search_steps, solution);
+        self.link.respond(
+            id,
+            SearchReturn::Finished {
+                search_id,
+                best,
+                progress,
+                search_tree,
+            },
+        );
+    }
+
+    fn progress(&self, search_steps: &SearchSteps, result: SearchResult<G>) -> SearchProgress {
+        SearchProgress {
+            hash: search_steps.game_state.zobrist_hash(),
+            step: search_steps.step,
+            total_steps: search_steps.stop.steps,
+            total_time_ns: search_steps.total_time_ns,
+            result,
+            action_stats: self.action_stats(search_steps),
+            worker_summaries: vec![],
+        }
+    }
+
+    /// Visit counts and mean values of the children of the search root.
+    pub fn root_action_stats(&self) -> Vec<ActionStats> {
+        root_action_stats(&self.search)
+    }
+
+    /// Copy of the search tree up to `max_depth` below the root, with children sorted by visit count.
+    pub fn search_tree(&self, max_depth: u8) -> Option<SearchTreeNode> {
+        let (_, root) = self.search.root?;
+        let initial_state = self.search.tree.get(root)?.data.state.clone();
+        // Nodes in depth-first order along with the index of their parent.
+        // Descendants always come after their ancestors.
+        let mut nodes: Vec<(Option<usize>, SearchTreeNode)> = vec![];
+        let mut stack = vec![(root, None, 0u8)];
+        while let Some((token, parent, depth)) = stack.pop() {
+            let Some(node) = self.search.tree.get(token) else {
+                continue;
+            };
+            let data = &node.data;
+            let value = if data.n > 0 {
+                data.w / (data.n as f32)
+            } else {
+                0.0
+            };
+            let index = nodes.len();
+            nodes.push((
+                parent,
+                SearchTreeNode {
+                    action: data
+                        .action
+                        .map(|action| describe_action_with_player(&initial_state, action)),
+                    visits: data.n,
+                    value,
+                    prior: data.prior,
+                    children: vec![],
+                },
+            ));
+            if depth < max_depth {
+                for child in token.children(&self.search.tree) {
+                    stack.push((child.token(), Some(index), depth + 1));
+                }
+            }
+        }
+        while let Some((parent, mut node)) = nodes.pop() {
+            node.children.sort_by_key(|c| std::cmp::Reverse(c.visits));
+            match parent {
+                Some(parent) => nodes[parent].1.children.push(Rc::new(node)),
+                None => return Some(node),
+            }
+        }
+        None
+    }
+
+    /// Root action statistics of the search, aggregated over the determinized states if any.
+    fn action_stats(&self, search_steps: &SearchSteps) -> Vec<ActionStats> {
+        if search_steps.determinized_stats.is_empty() {
+            return self.root_action_stats();
+        }
+        merge_action_stats(search_steps.determinized_stats.iter().flatten())
+    }
+
+    /// The head of the principal variation, or the most visited action over all determinized states.
+    fn best_action(&self, search_steps: &SearchSteps) -> Option<Input> {
+        if search_steps.determinized_stats.is_empty() {
+            return self.solution.as_ref().and_then(|s| s.pv.head());
+        }
+        most_visited(&self.action_stats(search_steps))
+    }
+
+    fn select_move(&self, search_steps: &SearchSteps) -> Option<Input> {
+        select_move(
+            &self.action_stats(search_steps),
+            self.best_action(search_steps),
+            search_steps.move_selection,
+        )
+    }
+}
+
+/// Visit counts and mean values of the children of the root of `search`.
+///
+/// `w` and `n` of a node are the total reward and the number of visits that the MCTS of
+/// `gitcg_sim_search` backpropagates through it. Rewards are from the point of view of the
+/// `maximize_player` passed to `MCTS::search` and range from 0 (loss) to 1 (win): a finished game
+/// scores its result, and other leaves score the share of random playouts that player wins. So
+/// `w / n` is the expected win rate of the searched player after the action, which `win_estimate`,
+/// the analysis and the review rely on. `decided_position_values` checks the scale and perspective.
+fn root_action_stats(search: &MCTS<G, DefaultEvalPolicy, RuleBasedPuct>) -> Vec<ActionStats> {
+    let Some((_, root)) = search.root else {
+        return vec![];
+    };
+    root.children(&search.tree)
+        .filter_map(|node| {
+            let data = &node.data;
+            let action = data.action?;
+            let value = if data.n > 0 {
+                data.w / (data.n as f32)
+            } else {
+                0.0
+            };
+            Some(ActionStats {
+                action,
+                visits: data.n,
+                value,
+            })
+        })
+        .collect()
+}
+
+/// Combine root action statistics from several searches of the same position: visits are added
+/// and values are averaged weighted by visits.
+pub fn merge_action_stats<'a>(
+    stats: impl IntoIterator<Item = &'a ActionStats>,
+) -> Vec<ActionStats> {
+    let mut merged: Vec<ActionStats> = vec![];
+    for stats in stats {
+        let Some(m) = merged.iter_mut().find(|m| m.action == stats.action) else {
+            merged.push(*stats);
+            continue;
+        };
+        let visits = m.visits + stats.visits;
+        if visits > 0 {
+            m.value =
+                (m.value * m.visits as f32 + stats.value * stats.visits as f32) / visits as f32;
+        }
+        m.visits = visits;
+    }
+    merged
+}
+
+pub fn most_visited(stats: &[ActionStats]) -> Option<Input> {
+    stats.iter().max_by_key(|s| s.visits).map(|s| s.action)
+}
+
+/// Value of the most visited root action.
+pub fn best_value(stats: &[ActionStats]) -> Option<f32> {
+    stats.iter().max_by_key(|s| s.visits).map(|s| s.value)
+}
+
+/// Choose the move to play from the root action statistics. `best` is played unless sampling.
+pub fn select_move(
+    stats: &[ActionStats],
+    best: Option<Input>,
+    move_selection: MoveSelection,
+) -> Option<Input> {
+    let MoveSelection::Sample { temperature } = move_selection else {
+        return best;
+    };
+    let weights: Vec<(Input, f64)> = stats
+        .iter()
+        .map(|s| {
+            (
+                s.action,
+                (s.visits as f64).powf(1.0 / temperature.max(1e-3) as f64),
+            )
+        })
+        .collect();
+    let total: f64 = weights.iter().map(|(_, w)| w).sum();
+    if total <= 0.0 {
+        return best;
+    }
+    let mut r = js_sys::Math::random() * total;
+    for (action, w) in weights {
+        if r < w {
+            return Some(action);
+        }
+        r -= w;
+    }
+    best
+}
+
+/// What the main thread uses a search for.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum SearchPurpose {
+    /// Choose a move for an AI seat.
+    Move,
+    /// Analyze the position of a human seat without playing.
+    Analysis,
+    /// Search for an AI seat while its human opponent is to move.
+    Ponder,
+    /// Recommend moves to a human seat.
+    Hint,
+    /// Evaluate the human move at the given index of the move log after the game.
+    Review(usize),
+    /// Evaluate starting with the given character for an AI seat before the game.
+    StartingCharacter(PlayerId, u8),
+}
+
+impl SearchPurpose {
+    /// Whether the search follows the position being played, i.e. is started and stopped as the
+    /// player to move changes.
+    pub fn follows_position(self) -> bool {
+        matches!(
+            self,
+            SearchPurpose::Move | SearchPurpose::Analysis | SearchPurpose::Ponder
+        )
+    }
+}
+
+/// A search running on one or more workers.
+struct ActiveSearch {
+    search_id: SearchId,
+    purpose: SearchPurpose,
+    move_selection: MoveSelection,
+    /// Latest progress reported by each worker taking part in the search.
+    progress: Vec<Option<SearchProgress>>,
+    /// Move chosen by each worker that has finished.
+    best: Vec<Option<Input>>,
+    search_tree: Option<Rc<SearchTreeNode>>,
+}
+
+impl ActiveSearch {
+    fn is_finished(&self) -> bool {
+        self.best.iter().all(Option::is_some)
+    }
+
+    /// Progress of all workers combined. Counters are added up and root action statistics merged,
+    /// while the evaluation and principal variation are taken from the first worker that reported.
+    fn merged_progress(&self) -> Option<SearchProgress> {
+        let reported: Vec<(usize, &SearchProgress)> = self
+            .progress
+            .iter()
+            .enumerate()
+            .filter_map(|(i, p)| Some((i, p.as_ref()?)))
+            .collect();
+        let (_, first) = reported.first()?;
+        let mut merged = (*first).clone();
+        if self.progress.len() == 1 {
+            return Some(merged);
+        }
+        for (_, p) in &reported[1..] {
+            merged.result.counter.add_in_place(&p.result.counter);
+            merged.step = merged.step.min(p.step);
+            merged.total_time_ns = merged.total_time_ns.max(p.total_time_ns);
+        }
+        merged.action_stats =
+            merge_action_stats(reported.iter().flat_map(|(_, p)| &p.action_stats));
+        merged.worker_summaries = reported
+            .iter()
+            .map(|(i, p)| {
+                format!(
+                    "Worker {}: {}, {} states visited",
+                    i + 1,
+                    p.result.counter.summary(p.total_time_ns),
+                    p.result.counter.states_visited
+                )
+            })
+            .collect();
+        Some(merged)
+    }
+}
+
+/// Main thread side of the search workers. Runs each search on one or more workers in parallel,
+/// merges their results, and keeps track of the search whose responses are still expected, so
+/// that responses for abandoned or superseded searches are ignored.
+pub struct SearchBridge {
+    callback: Rc<dyn Fn((usize, SearchReturn))>,
+    bridges: Vec<Box<dyn Bridge<SearchWorker>>>,
+    next_search_id: SearchId,
+    active: Option<ActiveSearch>,
+    /// Searches abandoned by the main thread whose cancellation has not been reported yet.
+    abandoned: Vec<(SearchId, SearchPurpose)>,
+    config_sent: Option<MCTSConfig>,
+}
+
+impl SearchBridge {
+    /// `callback` receives the index of the responding worker along with each response, to be
+    /// passed back to `receive`.
+    pub fn new(callback: Rc<dyn Fn((usize, SearchReturn))>) -> Self {
+        let mut bridge = Self {
+            callback,
+            bridges: vec![],
+            next_search_id: 0,
+            active: None,
+            abandoned: vec![],
+            config_sent: None,
+        };
+        bridge.spawn_workers(1);
+        bridge
+    }
+
+    /// Spawn workers until there are at least `count` of them.
+    fn spawn_workers(&mut self, count: usize) {
+        while self.bridges.len() < count {
+            let index = self.bridges.len();
+            let callback = self.callback.clone();
+            let mut bridge = SearchWorker::bridge(Rc::new(move |msg| callback((index, msg))));
+            if let Some(config) = self.config_sent {
+                bridge.send(SearchAction::SetConfig(config));
+            }
+            self.bridges.push(bridge);
+        }
+    }
+
+    /// Send the MCTS configuration of the settings to the workers unless it is already in use.
+    /// Settings that only affect when a search stops or how it is run keep the search trees.
+    pub fn set_config(&mut self, settings: &SearchSettings) {
+        let config = settings.to_config();
+        if self.config_sent == Some(config) {
+            return;
+        }
+        for bridge in &mut self.bridges {
+            bridge.send(SearchAction::SetConfig(config));
+        }
+        self.config_sent = Some(config);
+    }
+
+    /// Start searching the position on behalf of `maximize_player` until `stop`, replacing any active search.
+    /// The opponent's private information is hidden from the search.
+    pub fn start(
+        &mut self,
+        purpose: SearchPurpose,
+        settings: &SearchSettings,
+        stop: StopCondition,
+        maximize_player: PlayerId,
+        game_state: &Rc<G>,
+        move_selection: MoveSelection,
+        determinization: Option<Determinization>,
+    ) -> SearchId {
+        self.abandon();
+        self.set_config(settings);
+        let workers = settings.worker_count();
+        self.spawn_workers(workers);
+        let search_id = self.next_search_id;
+        self.next_search_id = self.next_search_id.wrapping_add(1);
+        self.active = Some(ActiveSearch {
+            search_id,
+            purpose,
+            move_selection,
+            progress: vec![None; workers],
+            best: vec![None; workers],
+            search_tree: None,
+        });
+        let mut game_state = game_state.clone();
+        Rc::make_mut(&mut game_state).hide_private_information(opponent(maximize_player));
+        for bridge in &mut self.bridges[..workers] {
+            bridge.send(SearchAction::Start {
+                search_id,
+                maximize_player,
+                game_state: game_state.clone(),
+                stop,
+                move_selection,
+                determinization: determinization.clone(),
+            });
+        }
+        search_id
+    }
+
+    /// Stop the active search. Its cancellation is reported once by `receive` as
+    /// `SearchReturn::Abandoned`, so that the caller can resume or skip what it was used for.
+    pub fn abandon(&mut self) {
+        if let Some(active) = self.stop_active() {
+            self.abandoned.push((active.search_id, active.purpose));
+        }
+    }
+
+    pub fn active_purpose(&self) -> Option<SearchPurpose> {
+        self.active.as_ref().map(|a| a.purpose)
+    }
+
+    /// Stop the active search only if it was started for a purpose accepted by `filter`.
+    pub fn abandon_if(&mut self, filter: impl Fn(SearchPurpose) -> bool) {
+        if self.active.as_ref().is_some_and(|a| filter(a.purpose)) {
+            self.abandon();
+        }
+    }
+
+    fn stop_active(&mut self) -> Option<ActiveSearch> {
+        let active = self.active.take()?;
+        for bridge in &mut self.bridges[..active.progress.len()] {
+            bridge.send(SearchAction::Abandon);
+        }
+        Some(active)
+    }
+
+    /// Record a response from a worker. Returns the purpose of the active search along with the
+    /// response to act on, combined over all workers, or `None` if the response is stale or other
+    /// workers have yet to finish.
+    pub fn receive(
+        &mut self,
+        worker: usize,
+        msg: SearchReturn,
+    ) -> Option<(SearchPurpose, SearchReturn)> {
+        let search_id = msg.search_id();
+        if let Some(i) = self.abandoned.iter().position(|&(id, _)| id == search_id) {
+            // Any final response of an abandoned search means that it stopped.
+            if matches!(
+                msg,
+                SearchReturn::Started { .. } | SearchReturn::Progress { .. }
+            ) {
+                return None;
+            }
+            let (_, purpose) = self.abandoned.remove(i);
+            return Some((purpose, SearchReturn::Abandoned { search_id }));
+        }
+        let active = self
+            .active
+            .as_mut()
+            .filter(|a| a.search_id == msg.search_id() && worker < a.progress.len())?;
+        let purpose = active.purpose;
+        match msg {
+            SearchReturn::Started { .. } => (worker == 0).then_some((purpose, msg)),
+            SearchReturn::Progress {
+                search_id,
+                progress,
+            } => {
+                active.progress[worker] = Some(progress);
+                let progress = active.merged_progress()?;
+                Some((
+                    purpose,
+                    SearchReturn::Progress {
+                        search_id,
+                        progress,
+                    },
+                ))
+            }
+            SearchReturn::Finished {
+                search_id,
+                best,
+                progress,
+                search_tree,
+            } => {
+                active.progress[worker] = Some(progress);
+                active.best[worker] = Some(best);
+                if worker == 0 {
+                    active.search_tree = search_tree;
+                }
+                if !active.is_finished() {
+                    return None;
+                }
+                let active = self.active.take()?;
+                let progress = active.merged_progress()?;
+                let best = if active.best.len() == 1 {
+                    Some(best)
+                } else {
+                    select_move(
+                        &progress.action_stats,
+                        most_visited(&progress.action_stats),
+                        active.move_selection,
+                    )
+                };
+                let best = best.or(active.best[0])?;
+                let msg = SearchReturn::Finished {
+                    search_id,
+                    best,
+                    progress,
+                    search_tree: active.search_tree,
+                };
+                Some((purpose, msg))
+            }
+            SearchReturn::Abandoned { .. } => {
+                self.active = None;
+                Some((purpose, msg))
+            }
+            SearchReturn::Error { .. } => {
+                self.stop_active();
+                Some((purpose, msg))
+            }
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::app::{default_decklists, new_game};
+
+    const END_ROUND: Input = Input::FromPlayer(PlayerId::PlayerFirst, PlayerAction::EndRound);
+    const SWITCH: Input =
+        Input::FromPlayer(PlayerId::PlayerFirst, PlayerAction::SwitchCharacter(1));
+
+    fn stats(action: Input, visits: u32, value: f32) -> ActionStats {
+        ActionStats {
+            action,
+            visits,
+            value,
+        }
+    }
+
+    /// The position before the last move of a game where skills are cast whenever possible,
+    /// along with that move and the winner.
+    fn decided_position() -> (G, Input, PlayerId) {
+        let decklists = default_decklists();
+        let mut game_state = new_game(&decklists, 1);
+        for _ in 0..10_000 {
+            let actions = game_state.actions();
+            let action = actions
+                .iter()
+                .copied()
+                .find(|a| matches!(a, Input::FromPlayer(_, PlayerAction::CastSkill(..))))
+                .unwrap_or(actions[0]);
+            let prev = game_state.clone();
+            game_state.advance(action).unwrap();
+            if let Some(winner) = game_state.winner() {
+                return (prev, action, winner);
+            }
+        }
+        panic!("the game did not end");
+    }
+
+    #[test]
+    fn decided_position_values() {
+        let (game_state, winning_move, winner) = decided_position();
+        let new_search = || {
+            MCTS::new_with_eval_policy_and_selection_policy(
+                DEFAULT_CONFIG,
+                Default::default(),
+                RuleBasedPuct,
+            )
+        };
+        // The winning move is worth a win for the winner and a loss for the opponent.
+        for (player_id, value) in [(winner, 1.0), (opponent(winner), 0.0)] {
+            let mut search = new_search();
+            search.search(&game_state, player_id);
+            let stats = root_action_stats(&search);
+            let winning = stats.iter().find(|s| s.action == winning_move).unwrap();
+            assert!(winning.visits > 0);
+            assert!((winning.value - value).abs() < 1e-6);
+            assert!(stats.iter().all(|s| (0.0..=1.0).contains(&s.value)));
+        }
+    }
+
+    #[test]
+    fn merge_action_stats_weights_values_by_visits() {
+        let worker1 = [stats(END_ROUND, 30, 0.2), stats(SWITCH, 10, 0.5)];
+        let worker2 = [stats(END_ROUND, 10, 0.6), stats(SWITCH, 0, 0.0)];
+        let merged = merge_action_stats(worker1.iter().chain(worker2.iter()));
+        assert_eq!(2, merged.len());
+        let end_round = merged.iter().find(|s| s.action == END_ROUND).unwrap();
+        assert_eq!(40, end_round.visits);
+        assert!((end_round.value - 0.3).abs() < 1e-6);
+        // Unvisited actions don't change the value.
+        let switch = merged.iter().find(|s| s.action == SWITCH).unwrap();
+        assert_eq!(10, switch.visits);
+        assert!((switch.value - 0.5).abs() < 1e-6);
     }
 }
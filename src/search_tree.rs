@@ -0,0 +1,74 @@
+use std::rc::Rc;
+
+use yew::prelude::*;
+
+use crate::search::SearchTreeNode;
+
+#[derive(Properties)]
+pub struct SearchTreeProps {
+    pub node: Rc<SearchTreeNode>,
+    #[prop_or(false)]
+    pub expanded: bool,
+}
+
+impl PartialEq for SearchTreeProps {
+    fn eq(&self, other: &Self) -> bool {
+        Rc::ptr_eq(&self.node, &other.node) && self.expanded == other.expanded
+    }
+}
+
+#[function_component(SearchTree)]
+pub fn search_tree(props: &SearchTreeProps) -> Html {
+    html! {
+        <div class="search-tree">
+            <ul>
+                <SearchTreeNodeView node={props.node.clone()} expanded={props.expanded} />
+            </ul>
+        </div>
+    }
+}
+
+#[function_component(SearchTreeNodeView)]
+fn search_tree_node_view(props: &SearchTreeProps) -> Html {
+    let expanded = use_state(|| props.expanded);
+    let node = &props.node;
+    let has_children = !node.children.is_empty();
+    let onclick = {
+        let expanded = expanded.clone();
+        Callback::from(move |_: MouseEvent| expanded.set(!*expanded))
+    };
+    html! {
+        <li class="search-tree-node">
+            <span
+                class={classes!("search-tree-label", if has_children { Some("has-children") } else { None })}
+                {onclick}
+            >
+                <span class="search-tree-toggle">
+                    {match (has_children, *expanded) {
+                        (false, _) => "\u{2022}",
+                        (true, false) => "\u{25b8}",
+                        (true, true) => "\u{25be}",
+                    }}
+                </span>
+                <span class="search-tree-action">
+                    {node.action.as_deref().unwrap_or("(Root)")}
+                </span>
+                <span class="search-tree-stats">
+                    {format!("N = {}, V = {:.3}", node.visits, node.value)}
+                    {node.prior.map(|p| format!(", P = {p:.3}")).unwrap_or_default()}
+                </span>
+            </span>
+            {if has_children && *expanded {
+                html! {
+                    <ul>
+                        {for node.children.iter().map(|child| html! {
+                            <SearchTreeNodeView node={child.clone()} />
+                        })}
+                    </ul>
+                }
+            } else {
+                html! {}
+            }}
+        </li>
+    }
+}
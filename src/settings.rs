@@ -0,0 +1,306 @@
+use std::str::FromStr;
+
+use gitcg_sim_search::{
+    mcts::{CpuctConfig, MCTSConfig},
+    prelude::*,
+};
+use gloo_storage::{LocalStorage, Storage};
+use serde::{Deserialize, Serialize};
+use wasm_bindgen::JsCast;
+use web_sys::HtmlInputElement;
+use yew::prelude::*;
+
+use crate::search::{MoveSelection, StopCondition, DEFAULT_CONFIG};
+
+const SEARCH_SETTINGS_KEY: &str = "search_settings";
+
+/// Step count saved before the search settings were introduced, used as the initial `steps`.
+const LEGACY_SEARCH_STEPS_KEY: &str = "search_steps";
+
+const DEFAULT_SEARCH_STEPS: u32 = 5;
+
+/// User-editable search parameters. Converted into an `MCTSConfig` for the search worker.
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct SearchSettings {
+    pub cpuct_init: f32,
+    pub cpuct_base: f32,
+    pub tt_size_mb: u32,
+    pub random_playout_iters: u32,
+    pub random_playout_cutoff: u32,
+    pub random_playout_bias: Option<f32>,
+    pub policy_bias: Option<f32>,
+    pub time_limit_ms: u32,
+    pub steps: u32,
+    /// Total time limit of a search across all steps.
+    #[serde(default)]
+    pub time_budget_ms: Option<u32>,
+    /// Stop searching once the best move has not changed for this many steps.
+    #[serde(default)]
+    pub converge_steps: Option<u32>,
+    /// Number of sampled hands for the opponent's hidden cards. Values below 2 search the hidden
+    /// state as is.
+    #[serde(default)]
+    pub determinizations: u32,
+    /// Number of workers searching in parallel, or 0 for one per logical processor.
+    #[serde(default = "default_workers")]
+    pub workers: u32,
+}
+
+fn default_workers() -> u32 {
+    1
+}
+
+/// Upper bound on the number of workers, whatever the number of logical processors.
+const MAX_WORKERS: usize = 16;
+
+impl Default for SearchSettings {
+    fn default() -> Self {
+        Self::from_config(&DEFAULT_CONFIG, DEFAULT_SEARCH_STEPS)
+    }
+}
+
+impl SearchSettings {
+    pub fn get_from_storage() -> Self {
+        LocalStorage::get(SEARCH_SETTINGS_KEY).unwrap_or_else(|_| {
+            let steps = LocalStorage::get(LEGACY_SEARCH_STEPS_KEY).unwrap_or(DEFAULT_SEARCH_STEPS);
+            Self::from_config(&DEFAULT_CONFIG, steps)
+        })
+    }
+
+    pub fn save_to_storage(&self) {
+        if let Err(e) = LocalStorage::set(SEARCH_SETTINGS_KEY, self) {
+            gloo::console::error!(format!("Can't save into localStorage: {:#?}", e));
+        }
+    }
+
+    pub fn from_config(config: &MCTSConfig, steps: u32) -> Self {
+        Self {
+            cpuct_init: config.cpuct.init as f32,
+            cpuct_base: config.cpuct.base as f32,
+            tt_size_mb: config.tt_size_mb as u32,
+            random_playout_iters: config.random_playout_iters as u32,
+            random_playout_cutoff: config.random_playout_cutoff as u32,
+            random_playout_bias: config.random_playout_bias.map(|b| b as f32),
+            policy_bias: config.policy_bias.map(|b| b as f32),
+            time_limit_ms: config
+                .limits
+                .as_ref()
+                .and_then(|l| l.max_time_ms)
+                .unwrap_or_default() as u32,
+            steps,
+            time_budget_ms: None,
+            converge_steps: None,
+            determinizations: 0,
+            workers: default_workers(),
+        }
+    }
+
+    pub fn worker_count(&self) -> usize {
+        let workers = match self.workers {
+            0 => gloo::utils::window().navigator().hardware_concurrency() as usize,
+            n => n as usize,
+        };
+        workers.clamp(1, MAX_WORKERS)
+    }
+
+    pub fn stop_condition(&self) -> StopCondition {
+        StopCondition {
+            steps: self.steps,
+            time_budget_ms: self.time_budget_ms,
+            converge_steps: self.converge_steps,
+        }
+    }
+
+    pub fn to_config(&self) -> MCTSConfig {
+        MCTSConfig {
+            cpuct: CpuctConfig {
+                init: self.cpuct_init as _,
+                base: self.cpuct_base as _,
+            },
+            tt_size_mb: self.tt_size_mb as _,
+            parallel: DEFAULT_CONFIG.parallel,
+            random_playout_iters: self.random_playout_iters as _,
+            random_playout_cutoff: self.random_playout_cutoff as _,
+            random_playout_bias: self.random_playout_bias.map(|b| b as _),
+            policy_bias: self.policy_bias.map(|b| b as _),
+            debug: DEFAULT_CONFIG.debug,
+            limits: Some(SearchLimits {
+                max_time_ms: Some(self.time_limit_ms as u128),
+                max_positions: None,
+            }),
+        }
+    }
+}
+
+/// Strength presets for an AI seat. `Custom` uses the settings from the AI settings panel.
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Serialize, Deserialize)]
+pub enum Difficulty {
+    Beginner,
+    Normal,
+    Hard,
+    Max,
+    Custom,
+}
+
+impl Difficulty {
+    pub const ALL: [Difficulty; 5] = [
+        Difficulty::Beginner,
+        Difficulty::Normal,
+        Difficulty::Hard,
+        Difficulty::Max,
+        Difficulty::Custom,
+    ];
+
+    pub fn name(self) -> &'static str {
+        match self {
+            Difficulty::Beginner => "Beginner",
+            Difficulty::Normal => "Normal",
+            Difficulty::Hard => "Hard",
+            Difficulty::Max => "Max",
+            Difficulty::Custom => "Custom",
+        }
+    }
+
+    pub fn search_settings(self, custom: &SearchSettings) -> SearchSettings {
+        let base = SearchSettings::default();
+        match self {
+            Difficulty::Beginner => SearchSettings {
+                random_playout_iters: 2,
+                time_limit_ms: 100,
+                steps: 1,
+                ..base
+            },
+            Difficulty::Normal => SearchSettings {
+                random_playout_iters: 5,
+                time_limit_ms: 250,
+                steps: 2,
+                ..base
+            },
+            Difficulty::Hard => base,
+            Difficulty::Max => SearchSettings {
+                tt_size_mb: 64,
+                time_limit_ms: 1000,
+                steps: 10,
+                ..base
+            },
+            Difficulty::Custom => custom.clone(),
+        }
+    }
+
+    /// Lower difficulties sample from the root visit distribution instead of always
+    /// playing the most visited move.
+    pub fn move_selection(self) -> MoveSelection {
+        match self {
+            Difficulty::Beginner => MoveSelection::Sample { temperature: 1.0 },
+            Difficulty::Normal => MoveSelection::Sample { temperature: 0.25 },
+            Difficulty::Hard | Difficulty::Max | Difficulty::Custom => MoveSelection::Best,
+        }
+    }
+}
+
+#[derive(Properties, PartialEq)]
+pub struct SearchSettingsFormProps {
+    pub settings: SearchSettings,
+    pub on_change: Callback<SearchSettings>,
+}
+
+#[function_component(SearchSettingsForm)]
+pub fn search_settings_form(props: &SearchSettingsFormProps) -> Html {
+    let draft = use_state(|| props.settings.clone());
+    {
+        let draft = draft.clone();
+        use_effect_with_deps(
+            move |settings| draft.set(settings.clone()),
+            props.settings.clone(),
+        );
+    }
+
+    fn input_value(e: Event) -> Option<String> {
+        e.target()
+            .and_then(|t| t.dyn_into::<HtmlInputElement>().ok())
+            .map(|input| input.value())
+    }
+
+    let field = |id: &'static str,
+                 title: &'static str,
+                 value: String,
+                 update: fn(&mut SearchSettings, &str) -> bool| {
+        let draft = draft.clone();
+        let onchange = Callback::from(move |e: Event| {
+            let Some(value) = input_value(e) else {
+                return;
+            };
+            let mut settings = (*draft).clone();
+            if update(&mut settings, value.trim()) {
+                draft.set(settings);
+            }
+        });
+        html! {
+            <label for={id}>
+                {title}
+                <input {id} type="text" {value} {onchange} />
+            </label>
+        }
+    };
+
+    fn parse<T: FromStr>(field: &mut T, value: &str) -> bool {
+        let Ok(v) = value.parse() else {
+            return false;
+        };
+        *field = v;
+        true
+    }
+
+    fn parse_opt<T: FromStr>(field: &mut Option<T>, value: &str) -> bool {
+        if value.is_empty() {
+            *field = None;
+            return true;
+        }
+        let Ok(v) = value.parse() else {
+            return false;
+        };
+        *field = Some(v);
+        true
+    }
+
+    fn opt_to_string<T: ToString>(value: Option<T>) -> String {
+        value.map(|v| v.to_string()).unwrap_or_default()
+    }
+
+    let on_apply = {
+        let draft = draft.clone();
+        let on_change = props.on_change.clone();
+        Callback::from(move |_: MouseEvent| on_change.emit((*draft).clone()))
+    };
+    let on_reset = {
+        let draft = draft.clone();
+        let on_change = props.on_change.clone();
+        Callback::from(move |_: MouseEvent| {
+            draft.set(SearchSettings::default());
+            on_change.emit(SearchSettings::default());
+        })
+    };
+
+    html! {
+        <div class="search-settings">
+            <h2>{"AI Settings"}</h2>
+            {field("cpuct-init", "C_puct init: ", draft.cpuct_init.to_string(), |s, v| parse(&mut s.cpuct_init, v))}
+            {field("cpuct-base", "C_puct base: ", draft.cpuct_base.to_string(), |s, v| parse(&mut s.cpuct_base, v))}
+            {field("tt-size-mb", "Transposition table size (MB): ", draft.tt_size_mb.to_string(), |s, v| parse(&mut s.tt_size_mb, v))}
+            {field("random-playout-iters", "Random playout iterations: ", draft.random_playout_iters.to_string(), |s, v| parse(&mut s.random_playout_iters, v))}
+            {field("random-playout-cutoff", "Random playout cutoff: ", draft.random_playout_cutoff.to_string(), |s, v| parse(&mut s.random_playout_cutoff, v))}
+            {field("random-playout-bias", "Random playout bias: ", opt_to_string(draft.random_playout_bias), |s, v| parse_opt(&mut s.random_playout_bias, v))}
+            {field("policy-bias", "Policy bias: ", opt_to_string(draft.policy_bias), |s, v| parse_opt(&mut s.policy_bias, v))}
+            {field("time-limit-ms", "Time limit per step (ms): ", draft.time_limit_ms.to_string(), |s, v| parse(&mut s.time_limit_ms, v))}
+            {field("search-steps", "Steps: ", draft.steps.to_string(), |s, v| parse(&mut s.steps, v))}
+            {field("time-budget-ms", "Total time budget (ms): ", opt_to_string(draft.time_budget_ms), |s, v| parse_opt(&mut s.time_budget_ms, v))}
+            {field("converge-steps", "Stop when best move is stable for (steps): ", opt_to_string(draft.converge_steps), |s, v| parse_opt(&mut s.converge_steps, v))}
+            {field("determinizations", "Sampled opponent hands: ", draft.determinizations.to_string(), |s, v| parse(&mut s.determinizations, v))}
+            {field("search-workers", "Search workers (0 = one per CPU): ", draft.workers.to_string(), |s, v| parse(&mut s.workers, v))}
+            <div class="search-settings-buttons">
+                <button onclick={on_apply} disabled={*draft == props.settings}>{"Apply"}</button>
+                <button onclick={on_reset}>{"Reset to Defaults"}</button>
+            </div>
+        </div>
+    }
+}
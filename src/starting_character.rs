@@ -0,0 +1,198 @@
+use std::rc::Rc;
+
+use gitcg_sim::prelude::*;
+use yew::prelude::*;
+
+use crate::{
+    app::{new_game, AppAction, AppState, Controllers, G},
+    components::Character,
+    state_edit::StateEdit,
+};
+
+/// A new game waiting for each seat to choose its starting active character.
+/// The game starts with the first character of each decklist active, so the choice is applied by
+/// a `StateEdit` on the created game, which keeps the decklists as they are.
+#[derive(Clone)]
+pub struct PendingStart {
+    pub decklists: Rc<(Decklist, Decklist)>,
+    pub seed: u64,
+    /// Index of the chosen character in the original decklist, for `PlayerFirst` and `PlayerSecond`.
+    pub choices: (Option<u8>, Option<u8>),
+    /// Candidates of the AI seats with their value from a short search: `None` until evaluated,
+    /// `Some(None)` if the search failed.
+    pub evaluations: Vec<(PlayerId, u8, Option<Option<f32>>)>,
+    /// Incremented to start the search of the next AI candidate again after it was stopped.
+    pub evaluation_attempt: u32,
+}
+
+impl PendingStart {
+    pub fn new(decklists: Rc<(Decklist, Decklist)>, seed: u64, controllers: &Controllers) -> Self {
+        let mut evaluations = vec![];
+        for player_id in [PlayerId::PlayerFirst, PlayerId::PlayerSecond] {
+            if !controllers.is_ai(player_id) {
+                continue;
+            }
+            let count = decklist(&decklists, player_id).characters.len() as u8;
+            evaluations.extend((0..count).map(|i| (player_id, i, None)));
+        }
+        Self {
+            decklists,
+            seed,
+            choices: (None, None),
+            evaluations,
+            evaluation_attempt: 0,
+        }
+    }
+
+    pub fn choice(&self, player_id: PlayerId) -> Option<u8> {
+        match player_id {
+            PlayerId::PlayerFirst => self.choices.0,
+            PlayerId::PlayerSecond => self.choices.1,
+        }
+    }
+
+    pub fn choose(&mut self, player_id: PlayerId, index: u8) {
+        match player_id {
+            PlayerId::PlayerFirst => self.choices.0 = Some(index),
+            PlayerId::PlayerSecond => self.choices.1 = Some(index),
+        }
+    }
+
+    /// The next AI candidate to evaluate.
+    pub fn next_evaluation(&self) -> Option<(PlayerId, u8)> {
+        self.evaluations
+            .iter()
+            .find(|(_, _, value)| value.is_none())
+            .map(|&(player_id, index, _)| (player_id, index))
+    }
+
+    /// Search the AI candidate again if its search was stopped before it finished.
+    pub fn restart_evaluation(&mut self, player_id: PlayerId, index: u8) {
+        if self.next_evaluation() == Some((player_id, index)) {
+            self.evaluation_attempt = self.evaluation_attempt.wrapping_add(1);
+        }
+    }
+
+    /// Record the value of an AI candidate. Once all candidates of the seat are evaluated, the
+    /// best one is chosen, or the first character if none could be evaluated.
+    pub fn set_evaluation(&mut self, player_id: PlayerId, index: u8, value: Option<f32>) {
+        let Some(evaluation) = self
+            .evaluations
+            .iter_mut()
+            .find(|(p, i, _)| *p == player_id && *i == index)
+        else {
+            return;
+        };
+        evaluation.2 = Some(value);
+        let candidates: Vec<_> = self
+            .evaluations
+            .iter()
+            .filter(|(p, _, _)| *p == player_id)
+            .collect();
+        if candidates.iter().any(|(_, _, value)| value.is_none()) {
+            return;
+        }
+        let best = candidates
+            .iter()
+            .filter_map(|&&(_, i, value)| Some((i, value??)))
+            .max_by(|(_, a), (_, b)| a.total_cmp(b))
+            .map(|(i, _)| i)
+            .unwrap_or_default();
+        self.choose(player_id, best);
+    }
+
+    /// The initial game state when `player_id` starts with the character at `index`, and the
+    /// other seat with its current choice.
+    pub fn candidate_game(&self, player_id: PlayerId, index: u8) -> G {
+        let mut pending = self.clone();
+        pending.choose(player_id, index);
+        let mut game_state = new_game(&self.decklists, self.seed);
+        for edit in pending.edits() {
+            edit.apply(&mut game_state);
+        }
+        game_state
+    }
+
+    /// The edits that make the chosen characters active.
+    pub fn edits(&self) -> Vec<StateEdit> {
+        [PlayerId::PlayerFirst, PlayerId::PlayerSecond]
+            .into_iter()
+            .map(|player_id| StateEdit::StartingCharacter {
+                player_id,
+                index: self.choice(player_id).unwrap_or_default(),
+            })
+            .collect()
+    }
+
+    pub fn is_complete(&self) -> bool {
+        self.choices.0.is_some() && self.choices.1.is_some()
+    }
+}
+
+fn decklist(decklists: &(Decklist, Decklist), player_id: PlayerId) -> &Decklist {
+    match player_id {
+        PlayerId::PlayerFirst => &decklists.0,
+        PlayerId::PlayerSecond => &decklists.1,
+    }
+}
+
+#[derive(Properties, PartialEq)]
+pub struct StartingCharacterSelectionProps {
+    pub app: UseReducerHandle<AppState>,
+}
+
+#[function_component(StartingCharacterSelection)]
+pub fn starting_character_selection(props: &StartingCharacterSelectionProps) -> Html {
+    let app = &props.app;
+    let Some(pending) = &app.pending_start else {
+        return html! {};
+    };
+    // Character cards as dealt, before any reordering.
+    let initial = new_game(&pending.decklists, pending.seed);
+    let hash = initial.zobrist_hash();
+    html! {
+        <div class="starting-character">
+            <h2>{"Choose Starting Characters"}</h2>
+            {for [PlayerId::PlayerFirst, PlayerId::PlayerSecond].into_iter().map(|player_id| {
+                let is_ai = app.controllers.is_ai(player_id);
+                let choice = pending.choice(player_id);
+                let status = match (is_ai, choice) {
+                    (_, Some(_)) => "Ready",
+                    (true, None) => "AI is choosing...",
+                    (false, None) => "Click a character",
+                };
+                let chars = initial.game_state.player(player_id).char_states();
+                html! {
+                    <div class={classes!("starting-character-seat", player_id.to_string())}>
+                        <h3>{format!("Player {player_id}: {status}")}</h3>
+                        <div class="starting-character-options">
+                            {for chars.iter_all().enumerate().map(|(i, c)| {
+                                let index = i as u8;
+                                let onclick = {
+                                    let app = app.clone();
+                                    Callback::from(move |_: MouseEvent| {
+                                        if !is_ai {
+                                            app.dispatch(AppAction::ChooseStartingCharacter(player_id, index));
+                                        }
+                                    })
+                                };
+                                html! {
+                                    <div class={classes!("starting-character-option", if is_ai { None } else { Some("is-selectable") })} {onclick}>
+                                        <Character
+                                            char_state={*c}
+                                            is_active={choice == Some(index)}
+                                            equip_statuses={vec![]}
+                                            char_statuses={vec![]}
+                                            team_statuses={vec![]}
+                                            {hash}
+                                        />
+                                    </div>
+                                }
+                            })}
+                        </div>
+                    </div>
+                }
+            })}
+        </div>
+    }
+}
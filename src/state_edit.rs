@@ -0,0 +1,58 @@
+use gitcg_sim::{deck::Deck, prelude::*};
+use serde::{Deserialize, Serialize};
+
+use crate::app::G;
+
+/// A change to the game state made by the frontend rather than through an `Input`, for the
+/// decisions that the simulator resolves on its own. Recorded in the move log and in replays.
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub enum StateEdit {
+    /// The player's starting active character, by index in the decklist.
+    StartingCharacter { player_id: PlayerId, index: u8 },
+    /// The player's hand and deck after redrawing cards of the opening hand.
+    Redraw {
+        player_id: PlayerId,
+        hand: Vec<CardId>,
+        deck: Vec<CardId>,
+    },
+}
+
+impl StateEdit {
+    pub fn apply(&self, game_state: &mut G) {
+        match self {
+            StateEdit::StartingCharacter { player_id, index } => {
+                game_state
+                    .game_state
+                    .player_mut(*player_id)
+                    .set_active_char_idx(*index);
+            }
+            StateEdit::Redraw {
+                player_id,
+                hand,
+                deck,
+            } => {
+                let player_hand = game_state.game_state.player_mut(*player_id).hand_mut();
+                player_hand.clear();
+                for &card_id in hand {
+                    player_hand.push(card_id);
+                }
+                *deck_mut(game_state, *player_id) = Deck::new(deck);
+            }
+        }
+        game_state.game_state.rehash();
+    }
+}
+
+pub fn deck(game_state: &G, player_id: PlayerId) -> &Deck {
+    match player_id {
+        PlayerId::PlayerFirst => &game_state.nd.state.decks.0,
+        PlayerId::PlayerSecond => &game_state.nd.state.decks.1,
+    }
+}
+
+pub fn deck_mut(game_state: &mut G, player_id: PlayerId) -> &mut Deck {
+    match player_id {
+        PlayerId::PlayerFirst => &mut game_state.nd.state.decks.0,
+        PlayerId::PlayerSecond => &mut game_state.nd.state.decks.1,
+    }
+}
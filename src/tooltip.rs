@@ -0,0 +1,102 @@
+use gitcg_sim::prelude::{card_defs::Status, tcg_model::*, *};
+use yew::prelude::*;
+
+use crate::actions_list::CostInfo;
+
+#[derive(Properties, PartialEq)]
+pub struct TooltipProps {
+    /// Shown while the pointer is over the children.
+    pub content: Html,
+    #[prop_or_default]
+    pub children: Children,
+}
+
+/// Wraps its children with a popover that is shown on hover. The popover is made of spans only, so
+/// that it can be placed inside buttons and other phrasing content.
+#[function_component(Tooltip)]
+pub fn tooltip(props: &TooltipProps) -> Html {
+    html! {
+        <span class="tooltip">
+            {for props.children.iter()}
+            <span class="tooltip-content">{props.content.clone()}</span>
+        </span>
+    }
+}
+
+#[derive(Properties, PartialEq)]
+pub struct CardDetailProps {
+    pub card_id: CardId,
+}
+
+#[function_component(CardDetail)]
+pub fn card_detail(props: &CardDetailProps) -> Html {
+    let card = props.card_id.card();
+    html! {
+        <span class="detail card-detail">
+            <span class="detail-title">{card.name}</span>
+            <span class="detail-line">{format!("Card type: {}", card.card_type)}</span>
+            <span class="detail-line">{"Cost: "}<CostInfo cost={card.cost} /></span>
+        </span>
+    }
+}
+
+#[derive(Properties, PartialEq)]
+pub struct SkillDetailProps {
+    pub skill_id: SkillId,
+}
+
+#[function_component(SkillDetail)]
+pub fn skill_detail(props: &SkillDetailProps) -> Html {
+    let skill = props.skill_id.skill();
+    html! {
+        <span class="detail skill-detail">
+            <span class="detail-title">{skill.name}</span>
+            <span class="detail-line">{format!("Skill type: {:?}", skill.skill_type)}</span>
+            <span class="detail-line">{"Cost: "}<CostInfo cost={skill.cost} /></span>
+            {for skill.deal_dmg.map(|dmg| html! {
+                <span class="detail-line">{describe_dmg(&dmg)}</span>
+            })}
+        </span>
+    }
+}
+
+pub fn describe_dmg(dmg: &DealDMG) -> String {
+    let mut description = format!("Deals {} {:?} DMG", dmg.dmg, dmg.dmg_type);
+    if dmg.piercing_dmg_to_standby > 0 {
+        description += &format!(
+            ", {} Piercing DMG to standby characters",
+            dmg.piercing_dmg_to_standby
+        );
+    }
+    description
+}
+
+#[derive(Properties)]
+pub struct StatusDetailProps {
+    pub status: &'static Status,
+    /// Kind of status shown in the popover, such as "Summon" or "Support".
+    pub kind: &'static str,
+}
+
+impl PartialEq for StatusDetailProps {
+    fn eq(&self, other: &Self) -> bool {
+        self.status.name == other.status.name && self.kind == other.kind
+    }
+}
+
+#[function_component(StatusDetail)]
+pub fn status_detail(props: &StatusDetailProps) -> Html {
+    let status = props.status;
+    html! {
+        <span class="detail status-detail">
+            <span class="detail-title">{status.name}</span>
+            <span class="detail-line">{props.kind}</span>
+            {for status.usages.map(|usages| html! {
+                <span class="detail-line">{format!("Usages: up to {usages}")}</span>
+            })}
+            {for status.duration_rounds.map(|rounds| html! {
+                <span class="detail-line">{format!("Lasts {rounds} round(s)")}</span>
+            })}
+        </span>
+    }
+}
@@ -0,0 +1,84 @@
+use gitcg_sim::prelude::*;
+use yew::prelude::*;
+
+use crate::{
+    app::AppState,
+    search::{best_value, SearchProgress},
+};
+
+const WIDTH: f32 = 600.0;
+const HEIGHT: f32 = 120.0;
+
+/// Estimated probability that `PlayerFirst` wins, from a search on behalf of `player_id`.
+/// Taken from the value of the most visited root action.
+pub fn win_estimate(player_id: PlayerId, progress: &SearchProgress) -> Option<f32> {
+    let value = best_value(&progress.action_stats)?;
+    Some(match player_id {
+        PlayerId::PlayerFirst => value,
+        PlayerId::PlayerSecond => 1.0 - value,
+    })
+}
+
+/// Move log indices after which a round ended, i.e. where the second player of the round
+/// declared the end of the round.
+fn round_boundaries(app: &AppState) -> Vec<usize> {
+    app.move_log
+        .iter()
+        .enumerate()
+        .filter(|(_, entry)| matches!(entry.action, Input::FromPlayer(_, PlayerAction::EndRound)))
+        .map(|(i, _)| i + 1)
+        .skip(1)
+        .step_by(2)
+        .collect()
+}
+
+#[derive(Properties, PartialEq)]
+pub struct WinProbabilityGraphProps {
+    pub app: UseReducerHandle<AppState>,
+}
+
+#[function_component(WinProbabilityGraph)]
+pub fn win_probability_graph(props: &WinProbabilityGraphProps) -> Html {
+    let app = &props.app;
+    let moves = app.move_log.len();
+    let estimates: Vec<(usize, f32)> = app
+        .win_estimates
+        .range(..=moves)
+        .map(|(&i, &p)| (i, p))
+        .collect();
+    if estimates.is_empty() {
+        return html! {};
+    }
+    let x = |i: usize| WIDTH * (i as f32) / (moves.max(1) as f32);
+    let y = |p: f32| HEIGHT * (1.0 - p.clamp(0.0, 1.0));
+    let points = |f: fn(f32) -> f32| -> String {
+        estimates
+            .iter()
+            .map(|&(i, p)| format!("{:.1},{:.1}", x(i), y(f(p))))
+            .collect::<Vec<_>>()
+            .join(" ")
+    };
+    let last_estimate = estimates[estimates.len() - 1].1;
+    html! {
+        <div class="win-graph">
+            <svg
+                viewBox={format!("-4 -4 {} {}", WIDTH + 8.0, HEIGHT + 8.0)}
+                preserveAspectRatio="none"
+            >
+                <line class="win-graph-even" x1="0" y1={y(0.5).to_string()} x2={WIDTH.to_string()} y2={y(0.5).to_string()} />
+                {for round_boundaries(app).into_iter().enumerate().map(|(round, i)| html! {
+                    <g class="win-graph-round">
+                        <line x1={x(i).to_string()} y1="0" x2={x(i).to_string()} y2={HEIGHT.to_string()} />
+                        <text x={(x(i) + 2.0).to_string()} y="10">{format!("R{}", round + 2)}</text>
+                    </g>
+                })}
+                <polyline class="win-graph-p1" points={points(|p| p)} />
+                <polyline class="win-graph-p2" points={points(|p| 1.0 - p)} />
+            </svg>
+            <div class="win-graph-legend">
+                <span class="win-graph-p1">{format!("{}: {:.0}%", PlayerId::PlayerFirst, 100.0 * last_estimate)}</span>
+                <span class="win-graph-p2">{format!("{}: {:.0}%", PlayerId::PlayerSecond, 100.0 * (1.0 - last_estimate))}</span>
+            </div>
+        </div>
+    }
+}